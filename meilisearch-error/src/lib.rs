@@ -54,14 +54,21 @@ pub enum Code {
     IndexNotFound,
     InvalidIndexUid,
     OpenIndex,
+    AliasNotFound,
+    InvalidAlias,
+    IndexSwapTimeout,
 
     // invalid state error
     InvalidState,
     MissingPrimaryKey,
     PrimaryKeyAlreadyPresent,
+    InvalidPrimaryKeyChange,
 
     MaxFieldsLimitExceeded,
     MissingDocumentId,
+    InvalidDocumentId,
+    InvalidDocumentEdition,
+    InvalidDocumentVersion,
 
     Facet,
     Filter,
@@ -84,6 +91,8 @@ pub enum Code {
     DumpAlreadyInProgress,
     DumpProcessFailed,
 
+    DatabaseSizeLimitReached,
+
     InvalidContentType,
     MissingContentType,
     MalformedPayload,
@@ -106,6 +115,10 @@ impl Code {
             OpenIndex => {
                 ErrCode::internal("index_not_accessible", StatusCode::INTERNAL_SERVER_ERROR)
             }
+            // thrown when requesting an unexisting alias
+            AliasNotFound => ErrCode::invalid("alias_not_found", StatusCode::NOT_FOUND),
+            InvalidAlias => ErrCode::invalid("invalid_alias", StatusCode::BAD_REQUEST),
+            IndexSwapTimeout => ErrCode::invalid("index_swap_timeout", StatusCode::CONFLICT),
 
             // invalid state error
             InvalidState => ErrCode::internal("invalid_state", StatusCode::INTERNAL_SERVER_ERROR),
@@ -123,6 +136,16 @@ impl Code {
                 ErrCode::invalid("max_fields_limit_exceeded", StatusCode::BAD_REQUEST)
             }
             MissingDocumentId => ErrCode::invalid("missing_document_id", StatusCode::BAD_REQUEST),
+            InvalidDocumentId => ErrCode::invalid("invalid_document_id", StatusCode::BAD_REQUEST),
+            InvalidDocumentEdition => {
+                ErrCode::invalid("invalid_document_edition", StatusCode::BAD_REQUEST)
+            }
+            InvalidDocumentVersion => {
+                ErrCode::invalid("invalid_document_version", StatusCode::BAD_REQUEST)
+            }
+            InvalidPrimaryKeyChange => {
+                ErrCode::invalid("invalid_primary_key_change", StatusCode::BAD_REQUEST)
+            }
 
             // error related to facets
             Facet => ErrCode::invalid("invalid_facet", StatusCode::BAD_REQUEST),
@@ -159,6 +182,11 @@ impl Code {
             DumpProcessFailed => {
                 ErrCode::internal("dump_process_failed", StatusCode::INTERNAL_SERVER_ERROR)
             }
+            // thrown when the LMDB map of an index is full and can't grow anymore
+            DatabaseSizeLimitReached => ErrCode::internal(
+                "database_size_limit_reached",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
             MissingContentType => {
                 ErrCode::invalid("missing_content_type", StatusCode::UNSUPPORTED_MEDIA_TYPE)
             }
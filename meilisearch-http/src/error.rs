@@ -15,6 +15,14 @@ pub enum MeilisearchHttpError {
     MissingContentType,
     #[error("The Content-Type \"{0}\" is invalid. Accepted values for the Content-Type header are: \"application/json\", \"application/x-ndjson\", \"text/csv\"")]
     InvalidContentType(String),
+    #[error("The timeout \"{0}\" is invalid. A timeout is a number of seconds, optionally followed by one of the units \"ms\", \"s\" or \"m\", of at most 5 minutes.")]
+    InvalidTimeout(String),
+    #[error("The webhook url \"{0}\" is invalid.")]
+    InvalidWebhookUrl(String),
+    #[error("At least one of the \"maxAge\" or \"maxCount\" parameters must be given to prune the tasks.")]
+    MissingRetentionPolicy,
+    #[error("The webhook url \"{0}\" can't be used, since MeiliSearch was built without the `analytics` feature, which provides the webhooks.")]
+    WebhooksDisabled(String),
 }
 
 impl ErrorCode for MeilisearchHttpError {
@@ -22,6 +30,10 @@ impl ErrorCode for MeilisearchHttpError {
         match self {
             MeilisearchHttpError::MissingContentType => Code::MissingContentType,
             MeilisearchHttpError::InvalidContentType(_) => Code::InvalidContentType,
+            MeilisearchHttpError::InvalidTimeout(_) => Code::BadRequest,
+            MeilisearchHttpError::InvalidWebhookUrl(_) => Code::BadRequest,
+            MeilisearchHttpError::MissingRetentionPolicy => Code::BadRequest,
+            MeilisearchHttpError::WebhooksDisabled(_) => Code::BadRequest,
         }
     }
 }
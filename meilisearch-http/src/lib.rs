@@ -8,6 +8,8 @@ pub mod analytics;
 pub mod helpers;
 pub mod option;
 pub mod routes;
+#[cfg(feature = "analytics")]
+pub mod webhook;
 use std::path::Path;
 use std::time::Duration;
 
@@ -18,6 +20,7 @@ use actix_web::web;
 
 use extractors::authentication::policies::*;
 use extractors::payload::PayloadConfig;
+use meilisearch_lib::index_controller::updates::RetentionPolicy;
 use meilisearch_lib::MeiliSearch;
 use sha2::Digest;
 
@@ -54,7 +57,17 @@ pub fn setup_meilisearch(opt: &Opt) -> anyhow::Result<MeiliSearch> {
         .set_ignore_snapshot_if_db_exists(opt.ignore_snapshot_if_db_exists)
         .set_dump_dst(opt.dumps_dir.clone())
         .set_snapshot_interval(Duration::from_secs(opt.snapshot_interval_sec))
-        .set_snapshot_dir(opt.snapshot_dir.clone());
+        .set_snapshot_dir(opt.snapshot_dir.clone())
+        .set_update_workers(opt.update_workers)
+        .set_notify_all_updates(cfg!(feature = "analytics") && opt.update_webhook_url.is_some())
+        .set_update_retention(RetentionPolicy {
+            max_age: opt.updates_retention_max_age_sec.map(Duration::from_secs),
+            max_count: opt.updates_retention_max_count,
+        });
+
+    if let Some(max_open_indexes) = opt.max_open_indexes {
+        meilisearch.set_max_open_indexes(max_open_indexes);
+    }
 
     if let Some(ref path) = opt.import_snapshot {
         meilisearch.set_import_snapshot(path.clone());
@@ -68,7 +81,18 @@ pub fn setup_meilisearch(opt: &Opt) -> anyhow::Result<MeiliSearch> {
         meilisearch.set_schedule_snapshot();
     }
 
-    meilisearch.build(opt.db_path.clone(), opt.indexer_options.clone())
+    let meilisearch = meilisearch.build(opt.db_path.clone(), opt.indexer_options.clone())?;
+
+    #[cfg(feature = "analytics")]
+    tokio::task::spawn(webhook::webhook_sender(
+        meilisearch.clone(),
+        opt.update_webhook_url.clone(),
+    ));
+    if cfg!(not(feature = "analytics")) && opt.update_webhook_url.is_some() {
+        log::warn!("the webhooks are disabled, since MeiliSearch was built without the `analytics` feature");
+    }
+
+    Ok(meilisearch)
 }
 
 /// Cleans and setup the temporary file folder in the database directory. This must be done after
@@ -44,6 +44,12 @@ pub struct Opt {
     #[structopt(long, env = "MEILI_MAX_INDEX_SIZE", default_value = "100 GiB")]
     pub max_index_size: Byte,
 
+    /// The maximum number of indexes kept open at the same time. The least recently used indexes
+    /// are closed when it is exceeded, and reopened when they are needed again. There is no limit
+    /// by default.
+    #[structopt(long, env = "MEILI_MAX_OPEN_INDEXES")]
+    pub max_open_indexes: Option<usize>,
+
     /// The maximum size, in bytes, of the update lmdb database directory
     #[structopt(long, env = "MEILI_MAX_UDB_SIZE", default_value = "100 GiB")]
     pub max_udb_size: Byte,
@@ -120,6 +126,26 @@ pub struct Opt {
     #[structopt(long, conflicts_with = "import-snapshot")]
     pub import_dump: Option<PathBuf>,
 
+    /// Defines the number of indexes whose updates can be processed concurrently. The updates of
+    /// an index are always processed one after the other.
+    #[structopt(long, env = "MEILI_UPDATE_WORKERS", default_value = "1")]
+    pub update_workers: usize,
+
+    /// Defines the age, in seconds, after which the finished updates are deleted from the update
+    /// history. The finished updates are kept forever by default.
+    #[structopt(long, env = "MEILI_UPDATES_RETENTION_MAX_AGE_SEC")]
+    pub updates_retention_max_age_sec: Option<u64>,
+
+    /// Defines the maximum number of finished updates kept in the update history of each index,
+    /// the oldest ones are deleted first.
+    #[structopt(long, env = "MEILI_UPDATES_RETENTION_MAX_COUNT")]
+    pub updates_retention_max_count: Option<usize>,
+
+    /// Defines the url to which the finished updates are posted. It can be overridden for each
+    /// update with the `webhook` query parameter.
+    #[structopt(long, env = "MEILI_UPDATE_WEBHOOK_URL")]
+    pub update_webhook_url: Option<String>,
+
     /// Set the log level
     #[structopt(long, env = "MEILI_LOG_LEVEL", default_value = "info")]
     pub log_level: String,
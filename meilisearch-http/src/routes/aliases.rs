@@ -0,0 +1,67 @@
+use actix_web::{web, HttpResponse};
+use log::debug;
+use meilisearch_lib::MeiliSearch;
+use serde::Deserialize;
+
+use crate::error::ResponseError;
+use crate::extractors::authentication::{policies::*, GuardedData};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("").route(web::get().to(list_aliases)))
+        .service(
+            web::resource("/{alias}")
+                .route(web::get().to(get_alias))
+                .route(web::put().to(put_alias))
+                .route(web::delete().to(delete_alias)),
+        );
+}
+
+#[derive(Deserialize)]
+pub struct AliasParam {
+    alias: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PutAliasRequest {
+    index_uid: String,
+}
+
+pub async fn list_aliases(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+) -> Result<HttpResponse, ResponseError> {
+    let aliases = meilisearch.list_aliases().await?;
+    debug!("returns: {:?}", aliases);
+    Ok(HttpResponse::Ok().json(aliases))
+}
+
+pub async fn get_alias(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    path: web::Path<AliasParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let alias = meilisearch.get_alias(path.into_inner().alias).await?;
+    debug!("returns: {:?}", alias);
+    Ok(HttpResponse::Ok().json(alias))
+}
+
+/// Creates the alias, or makes it point to another index if it already exists.
+pub async fn put_alias(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    path: web::Path<AliasParam>,
+    body: web::Json<PutAliasRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    debug!("called with params: {:?}", body);
+    let alias = meilisearch
+        .put_alias(path.into_inner().alias, body.into_inner().index_uid)
+        .await?;
+    debug!("returns: {:?}", alias);
+    Ok(HttpResponse::Ok().json(alias))
+}
+
+pub async fn delete_alias(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    path: web::Path<AliasParam>,
+) -> Result<HttpResponse, ResponseError> {
+    meilisearch.delete_alias(path.into_inner().alias).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -13,7 +13,7 @@ use tokio::sync::mpsc;
 use crate::error::{MeilisearchHttpError, ResponseError};
 use crate::extractors::authentication::{policies::*, GuardedData};
 use crate::extractors::payload::Payload;
-use crate::routes::IndexParam;
+use crate::routes::{IndexParam, UpdateOptionsQuery};
 
 const DEFAULT_RETRIEVE_DOCUMENTS_OFFSET: usize = 0;
 const DEFAULT_RETRIEVE_DOCUMENTS_LIMIT: usize = 20;
@@ -45,6 +45,7 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     )
     // this route needs to be before the /documents/{document_id} to match properly
     .service(web::resource("/delete-batch").route(web::post().to(delete_documents)))
+    .service(web::resource("/edit").route(web::post().to(edit_documents)))
     .service(
         web::resource("/{document_id}")
             .route(web::get().to(get_document))
@@ -68,6 +69,7 @@ pub async fn get_document(
 pub async fn delete_document(
     meilisearch: GuardedData<Private, MeiliSearch>,
     path: web::Path<DocumentParam>,
+    params: web::Query<UpdateOptionsQuery>,
 ) -> Result<HttpResponse, ResponseError> {
     let DocumentParam {
         document_id,
@@ -75,7 +77,12 @@ pub async fn delete_document(
     } = path.into_inner();
     let update = Update::DeleteDocuments(vec![document_id]);
     let update_status = meilisearch
-        .register_update(index_uid, update, false)
+        .register_update_with_options(
+            index_uid,
+            update,
+            false,
+            params.into_inner().into_options()?,
+        )
         .await?;
     debug!("returns: {:?}", update_status);
     Ok(HttpResponse::Accepted().json(serde_json::json!({ "updateId": update_status.id() })))
@@ -122,6 +129,18 @@ pub async fn get_all_documents(
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct UpdateDocumentsQuery {
     primary_key: Option<String>,
+    /// Field holding the version of the documents, the documents older than the stored ones are
+    /// skipped.
+    version_field: Option<String>,
+    /// Only validate the payload, without registering the update.
+    #[serde(default)]
+    dry_run: bool,
+    /// Skip the invalid documents instead of failing the whole update.
+    #[serde(default)]
+    lenient: bool,
+    #[serde(default)]
+    priority: u8,
+    webhook: Option<String>,
 }
 
 pub async fn add_documents(
@@ -138,7 +157,7 @@ pub async fn add_documents(
             .map(|s| s.to_str().unwrap_or("unkown")),
         meilisearch,
         path.into_inner().index_uid,
-        params.into_inner().primary_key,
+        params.into_inner(),
         body,
         IndexDocumentsMethod::ReplaceDocuments,
     )
@@ -159,7 +178,7 @@ pub async fn update_documents(
             .map(|s| s.to_str().unwrap_or("unkown")),
         meilisearch,
         path.into_inner().index_uid,
-        params.into_inner().primary_key,
+        params.into_inner(),
         body,
         IndexDocumentsMethod::UpdateDocuments,
     )
@@ -172,7 +191,7 @@ async fn document_addition(
     content_type: Option<&str>,
     meilisearch: GuardedData<Private, MeiliSearch>,
     index_uid: String,
-    primary_key: Option<String>,
+    params: UpdateDocumentsQuery,
     body: Payload,
     method: IndexDocumentsMethod,
 ) -> Result<HttpResponse, ResponseError> {
@@ -186,14 +205,37 @@ async fn document_addition(
         None => return Err(MeilisearchHttpError::MissingContentType.into()),
     };
 
+    if params.dry_run {
+        let validation = meilisearch
+            .validate_documents(
+                index_uid,
+                Box::new(payload_to_stream(body)),
+                format,
+                params.primary_key,
+            )
+            .await?;
+
+        debug!("returns: {:?}", validation);
+        return Ok(HttpResponse::Ok().json(validation));
+    }
+
+    let options = UpdateOptionsQuery {
+        priority: params.priority,
+        webhook: params.webhook,
+    }
+    .into_options()?;
     let update = Update::DocumentAddition {
         payload: Box::new(payload_to_stream(body)),
-        primary_key,
+        primary_key: params.primary_key,
         method,
         format,
+        version_field: params.version_field,
+        lenient: params.lenient,
     };
 
-    let update_status = meilisearch.register_update(index_uid, update, true).await?;
+    let update_status = meilisearch
+        .register_update_with_options(index_uid, update, true, options)
+        .await?;
 
     debug!("returns: {:?}", update_status);
     Ok(HttpResponse::Accepted().json(serde_json::json!({ "updateId": update_status.id() })))
@@ -202,6 +244,7 @@ async fn document_addition(
 pub async fn delete_documents(
     meilisearch: GuardedData<Private, MeiliSearch>,
     path: web::Path<IndexParam>,
+    params: web::Query<UpdateOptionsQuery>,
     body: web::Json<Vec<Value>>,
 ) -> Result<HttpResponse, ResponseError> {
     debug!("called with params: {:?}", body);
@@ -216,7 +259,42 @@ pub async fn delete_documents(
 
     let update = Update::DeleteDocuments(ids);
     let update_status = meilisearch
-        .register_update(path.into_inner().index_uid, update, false)
+        .register_update_with_options(
+            path.into_inner().index_uid,
+            update,
+            false,
+            params.into_inner().into_options()?,
+        )
+        .await?;
+    debug!("returns: {:?}", update_status);
+    Ok(HttpResponse::Accepted().json(serde_json::json!({ "updateId": update_status.id() })))
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DocumentEditionRequest {
+    function: String,
+    filter: Option<Value>,
+}
+
+/// Applies a function to every document matching the optional filter. The function is a Rhai
+/// script that can read and modify the current document through the `doc` variable.
+pub async fn edit_documents(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    path: web::Path<IndexParam>,
+    params: web::Query<UpdateOptionsQuery>,
+    body: web::Json<DocumentEditionRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    debug!("called with params: {:?}", body);
+    let DocumentEditionRequest { function, filter } = body.into_inner();
+    let update = Update::DocumentEdition { function, filter };
+    let update_status = meilisearch
+        .register_update_with_options(
+            path.into_inner().index_uid,
+            update,
+            false,
+            params.into_inner().into_options()?,
+        )
         .await?;
     debug!("returns: {:?}", update_status);
     Ok(HttpResponse::Accepted().json(serde_json::json!({ "updateId": update_status.id() })))
@@ -225,10 +303,16 @@ pub async fn delete_documents(
 pub async fn clear_all_documents(
     meilisearch: GuardedData<Private, MeiliSearch>,
     path: web::Path<IndexParam>,
+    params: web::Query<UpdateOptionsQuery>,
 ) -> Result<HttpResponse, ResponseError> {
     let update = Update::ClearDocuments;
     let update_status = meilisearch
-        .register_update(path.into_inner().index_uid, update, false)
+        .register_update_with_options(
+            path.into_inner().index_uid,
+            update,
+            false,
+            params.into_inner().into_options()?,
+        )
         .await?;
     debug!("returns: {:?}", update_status);
     Ok(HttpResponse::Accepted().json(serde_json::json!({ "updateId": update_status.id() })))
@@ -1,13 +1,15 @@
 use actix_web::{web, HttpResponse};
 use chrono::{DateTime, Utc};
 use log::debug;
-use meilisearch_lib::index_controller::IndexSettings;
+use meilisearch_lib::index::IndexUserMetadata;
+use meilisearch_lib::index_controller::{IndexMetadata, IndexSettings, Update};
+use meilisearch_lib::milli::update::Setting;
 use meilisearch_lib::MeiliSearch;
 use serde::{Deserialize, Serialize};
 
 use crate::error::ResponseError;
 use crate::extractors::authentication::{policies::*, GuardedData};
-use crate::routes::IndexParam;
+use crate::routes::{IndexParam, UpdateOptionsQuery};
 
 pub mod documents;
 pub mod search;
@@ -29,6 +31,8 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                     .route(web::delete().to(delete_index)),
             )
             .service(web::resource("/stats").route(web::get().to(get_index_stats)))
+            .service(web::resource("/clone").route(web::post().to(clone_index)))
+            .service(web::resource("/primary-key").route(web::put().to(change_primary_key)))
             .service(web::scope("/documents").configure(documents::configure))
             .service(web::scope("/search").configure(search::configure))
             .service(web::scope("/updates").configure(updates::configure))
@@ -36,12 +40,48 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     );
 }
 
+const DEFAULT_LIST_INDEXES_OFFSET: usize = 0;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ListIndexesQuery {
+    offset: Option<usize>,
+    /// Every index is listed if there is no limit.
+    limit: Option<usize>,
+    /// Only the indexes whose uid starts with this prefix are listed.
+    uid_prefix: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListIndexesResponse {
+    results: Vec<IndexMetadata>,
+    offset: usize,
+    limit: Option<usize>,
+    /// The number of indexes matching the query, in all the pages.
+    total: usize,
+}
+
 pub async fn list_indexes(
     data: GuardedData<Private, MeiliSearch>,
+    params: web::Query<ListIndexesQuery>,
 ) -> Result<HttpResponse, ResponseError> {
-    let indexes = data.list_indexes().await?;
-    debug!("returns: {:?}", indexes);
-    Ok(HttpResponse::Ok().json(indexes))
+    debug!("called with params: {:?}", params);
+    let params = params.into_inner();
+    let offset = params.offset.unwrap_or(DEFAULT_LIST_INDEXES_OFFSET);
+    let limit = params.limit;
+
+    let (total, results) = data.list_indexes(params.uid_prefix, offset, limit).await?;
+
+    let response = ListIndexesResponse {
+        results,
+        offset,
+        limit,
+        total,
+    };
+
+    debug!("returns: {:?}", response);
+    Ok(HttpResponse::Ok().json(response))
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,6 +89,11 @@ pub async fn list_indexes(
 pub struct IndexCreateRequest {
     uid: String,
     primary_key: Option<String>,
+    /// The size, in bytes, of the LMDB map of the index. Defaults to the max index size.
+    map_size: Option<usize>,
+    /// The display name of the index.
+    name: Option<String>,
+    metadata: Option<IndexUserMetadata>,
 }
 
 pub async fn create_index(
@@ -56,7 +101,15 @@ pub async fn create_index(
     body: web::Json<IndexCreateRequest>,
 ) -> Result<HttpResponse, ResponseError> {
     let body = body.into_inner();
-    let meta = meilisearch.create_index(body.uid, body.primary_key).await?;
+    let meta = meilisearch
+        .create_index(
+            body.uid,
+            body.primary_key,
+            body.map_size,
+            body.name,
+            body.metadata,
+        )
+        .await?;
     Ok(HttpResponse::Created().json(meta))
 }
 
@@ -65,6 +118,10 @@ pub async fn create_index(
 pub struct UpdateIndexRequest {
     uid: Option<String>,
     primary_key: Option<String>,
+    metadata: Option<IndexUserMetadata>,
+    /// The display name of the index is removed when it is set to `null`.
+    #[serde(default)]
+    name: Setting<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -96,6 +153,8 @@ pub async fn update_index(
     let settings = IndexSettings {
         uid: body.uid,
         primary_key: body.primary_key,
+        metadata: body.metadata,
+        name: body.name,
     };
     let meta = meilisearch
         .update_index(path.into_inner().index_uid, settings)
@@ -112,6 +171,60 @@ pub async fn delete_index(
     Ok(HttpResponse::NoContent().finish())
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct CloneIndexRequest {
+    uid: String,
+    #[serde(default)]
+    copy_documents: bool,
+}
+
+/// Creates a new index with the primary key and the settings of the index, and optionally its
+/// documents.
+pub async fn clone_index(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    path: web::Path<IndexParam>,
+    body: web::Json<CloneIndexRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    debug!("called with params: {:?}", body);
+    let body = body.into_inner();
+    let meta = meilisearch
+        .clone_index(path.into_inner().index_uid, body.uid, body.copy_documents)
+        .await?;
+    debug!("returns: {:?}", meta);
+    Ok(HttpResponse::Created().json(meta))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ChangePrimaryKeyRequest {
+    primary_key: String,
+}
+
+/// Registers an update changing the primary key of the index, which reindexes its documents
+/// with their new ids.
+pub async fn change_primary_key(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    path: web::Path<IndexParam>,
+    params: web::Query<UpdateOptionsQuery>,
+    body: web::Json<ChangePrimaryKeyRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    debug!("called with params: {:?}", body);
+    let update = Update::PrimaryKeyChange {
+        primary_key: body.into_inner().primary_key,
+    };
+    let update_status = meilisearch
+        .register_update_with_options(
+            path.into_inner().index_uid,
+            update,
+            false,
+            params.into_inner().into_options()?,
+        )
+        .await?;
+    debug!("returns: {:?}", update_status);
+    Ok(HttpResponse::Accepted().json(serde_json::json!({ "updateId": update_status.id() })))
+}
+
 pub async fn get_index_stats(
     meilisearch: GuardedData<Private, MeiliSearch>,
     path: web::Path<IndexParam>,
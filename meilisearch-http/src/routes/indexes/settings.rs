@@ -7,6 +7,7 @@ use meilisearch_lib::MeiliSearch;
 
 use crate::error::ResponseError;
 use crate::extractors::authentication::{policies::*, GuardedData};
+use crate::routes::UpdateOptionsQuery;
 
 #[macro_export]
 macro_rules! make_setting_route {
@@ -20,17 +21,21 @@ macro_rules! make_setting_route {
 
             use crate::error::ResponseError;
             use crate::extractors::authentication::{GuardedData, policies::*};
+            use crate::routes::UpdateOptionsQuery;
 
             pub async fn delete(
                 meilisearch: GuardedData<Private, MeiliSearch>,
                 index_uid: web::Path<String>,
+                params: web::Query<UpdateOptionsQuery>,
             ) -> Result<HttpResponse, ResponseError> {
                 let settings = Settings {
                     $attr: Setting::Reset,
                     ..Default::default()
                 };
                 let update = Update::Settings(settings);
-                let update_status = meilisearch.register_update(index_uid.into_inner(), update, false).await?;
+                let update_status = meilisearch
+                    .register_update_with_options(index_uid.into_inner(), update, false, params.into_inner().into_options()?)
+                    .await?;
                 debug!("returns: {:?}", update_status);
                 Ok(HttpResponse::Accepted().json(serde_json::json!({ "updateId": update_status.id() })))
             }
@@ -38,6 +43,7 @@ macro_rules! make_setting_route {
             pub async fn update(
                 meilisearch: GuardedData<Private, MeiliSearch>,
                 index_uid: actix_web::web::Path<String>,
+                params: web::Query<UpdateOptionsQuery>,
                 body: actix_web::web::Json<Option<$type>>,
             ) -> std::result::Result<HttpResponse, ResponseError> {
                 let settings = Settings {
@@ -49,7 +55,9 @@ macro_rules! make_setting_route {
                 };
 
                 let update = Update::Settings(settings);
-                let update_status = meilisearch.register_update(index_uid.into_inner(), update, true).await?;
+                let update_status = meilisearch
+                    .register_update_with_options(index_uid.into_inner(), update, true, params.into_inner().into_options()?)
+                    .await?;
                 debug!("returns: {:?}", update_status);
                 Ok(HttpResponse::Accepted().json(serde_json::json!({ "updateId": update_status.id() })))
             }
@@ -153,13 +161,19 @@ generate_configure!(
 pub async fn update_all(
     meilisearch: GuardedData<Private, MeiliSearch>,
     index_uid: web::Path<String>,
+    params: web::Query<UpdateOptionsQuery>,
     body: web::Json<Settings<Unchecked>>,
 ) -> Result<HttpResponse, ResponseError> {
     let settings = body.into_inner();
 
     let update = Update::Settings(settings);
     let update_result = meilisearch
-        .register_update(index_uid.into_inner(), update, true)
+        .register_update_with_options(
+            index_uid.into_inner(),
+            update,
+            true,
+            params.into_inner().into_options()?,
+        )
         .await?;
     let json = serde_json::json!({ "updateId": update_result.id() });
     debug!("returns: {:?}", json);
@@ -178,12 +192,18 @@ pub async fn get_all(
 pub async fn delete_all(
     data: GuardedData<Private, MeiliSearch>,
     index_uid: web::Path<String>,
+    params: web::Query<UpdateOptionsQuery>,
 ) -> Result<HttpResponse, ResponseError> {
     let settings = Settings::cleared();
 
     let update = Update::Settings(settings.into_unchecked());
     let update_result = data
-        .register_update(index_uid.into_inner(), update, false)
+        .register_update_with_options(
+            index_uid.into_inner(),
+            update,
+            false,
+            params.into_inner().into_options()?,
+        )
         .await?;
     let json = serde_json::json!({ "updateId": update_result.id() });
     debug!("returns: {:?}", json);
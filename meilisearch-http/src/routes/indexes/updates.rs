@@ -1,18 +1,26 @@
+use std::time::Duration;
+
 use actix_web::{web, HttpResponse};
 use chrono::{DateTime, Utc};
 use log::debug;
 use meilisearch_lib::MeiliSearch;
 use serde::{Deserialize, Serialize};
 
-use crate::error::ResponseError;
+use crate::error::{MeilisearchHttpError, ResponseError};
 use crate::extractors::authentication::{policies::*, GuardedData};
 use crate::routes::{IndexParam, UpdateStatusResponse};
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("").route(web::get().to(get_all_updates_status)))
-        .service(web::resource("{update_id}").route(web::get().to(get_update_status)));
+        .service(web::resource("{update_id}").route(web::get().to(get_update_status)))
+        .service(web::resource("{update_id}/wait").route(web::get().to(wait_update_status)));
 }
 
+/// How long a wait for an update lasts when no timeout is given.
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+/// The longest a wait for an update can last.
+const MAX_WAIT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 struct UpdateIndexRequest {
@@ -49,6 +57,54 @@ pub async fn get_update_status(
     Ok(HttpResponse::Ok().json(meta))
 }
 
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct WaitUpdateQuery {
+    timeout: Option<String>,
+}
+
+/// Parses a timeout such as `500ms`, `30s` or `1m`. A number without unit is a number of seconds.
+fn parse_timeout(timeout: &str) -> Result<Duration, MeilisearchHttpError> {
+    let invalid = || MeilisearchHttpError::InvalidTimeout(timeout.to_string());
+    let (value, unit) = match timeout.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => timeout.split_at(index),
+        None => (timeout, "s"),
+    };
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    let duration = match unit {
+        "ms" => Duration::from_millis(value),
+        "s" => Duration::from_secs(value),
+        "m" => Duration::from_secs(value.checked_mul(60).ok_or_else(invalid)?),
+        _ => return Err(invalid()),
+    };
+
+    if duration > MAX_WAIT_TIMEOUT {
+        return Err(invalid());
+    }
+
+    Ok(duration)
+}
+
+/// Returns the status of the update as soon as it is processed, failed or aborted, or once the
+/// timeout is reached.
+pub async fn wait_update_status(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    path: web::Path<UpdateParam>,
+    params: web::Query<WaitUpdateQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let timeout = match params.into_inner().timeout {
+        Some(timeout) => parse_timeout(&timeout)?,
+        None => DEFAULT_WAIT_TIMEOUT,
+    };
+    let params = path.into_inner();
+    let meta = meilisearch
+        .wait_update(params.index_uid, params.update_id, timeout)
+        .await?;
+    let meta = UpdateStatusResponse::from(meta);
+    debug!("returns: {:?}", meta);
+    Ok(HttpResponse::Ok().json(meta))
+}
+
 pub async fn get_all_updates_status(
     meilisearch: GuardedData<Private, MeiliSearch>,
     path: web::Path<IndexParam>,
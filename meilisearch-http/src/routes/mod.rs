@@ -3,18 +3,25 @@ use std::time::Duration;
 use actix_web::{web, HttpResponse};
 use chrono::{DateTime, Utc};
 use log::debug;
-use meilisearch_lib::index_controller::updates::status::{UpdateResult, UpdateStatus};
+use meilisearch_lib::index_controller::updates::status::{
+    IndexingProgress, UpdateResult, UpdateStatus,
+};
+use meilisearch_lib::index_controller::updates::UpdateOptions;
+use meilisearch_lib::index_controller::validation::InvalidDocument;
 use serde::{Deserialize, Serialize};
 
 use meilisearch_lib::index::{Settings, Unchecked};
 use meilisearch_lib::{MeiliSearch, Update};
 
-use crate::error::ResponseError;
+use crate::error::{MeilisearchHttpError, ResponseError};
 use crate::extractors::authentication::{policies::*, GuardedData};
 use crate::ApiKeys;
 
+mod aliases;
 mod dump;
 mod indexes;
+mod swap_indexes;
+mod tasks;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("/health").route(web::get().to(get_health)))
@@ -22,7 +29,10 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .service(web::resource("/keys").route(web::get().to(list_keys)))
         .service(web::resource("/stats").route(web::get().to(get_stats)))
         .service(web::resource("/version").route(web::get().to(get_version)))
-        .service(web::scope("/indexes").configure(indexes::configure));
+        .service(web::scope("/indexes").configure(indexes::configure))
+        .service(web::scope("/aliases").configure(aliases::configure))
+        .service(web::scope("/swap-indexes").configure(swap_indexes::configure))
+        .service(web::scope("/tasks").configure(tasks::configure));
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,18 +44,47 @@ pub enum UpdateType {
     DocumentsAddition {
         #[serde(skip_serializing_if = "Option::is_none")]
         number: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        skipped: Option<usize>,
     },
     DocumentsPartial {
         #[serde(skip_serializing_if = "Option::is_none")]
         number: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        skipped: Option<usize>,
     },
     DocumentsDeletion {
         #[serde(skip_serializing_if = "Option::is_none")]
         number: Option<usize>,
     },
+    DocumentsEdition {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        number: Option<usize>,
+    },
     Settings {
         settings: Settings<Unchecked>,
     },
+    #[serde(rename_all = "camelCase")]
+    PrimaryKeyChange {
+        primary_key: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        number: Option<usize>,
+    },
+}
+
+impl UpdateType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpdateType::ClearAll => "ClearAll",
+            UpdateType::Customs => "Customs",
+            UpdateType::DocumentsAddition { .. } => "DocumentsAddition",
+            UpdateType::DocumentsPartial { .. } => "DocumentsPartial",
+            UpdateType::DocumentsDeletion { .. } => "DocumentsDeletion",
+            UpdateType::DocumentsEdition { .. } => "DocumentsEdition",
+            UpdateType::Settings { .. } => "Settings",
+            UpdateType::PrimaryKeyChange { .. } => "PrimaryKeyChange",
+        }
+    }
 }
 
 impl From<&UpdateStatus> for UpdateType {
@@ -53,19 +92,19 @@ impl From<&UpdateStatus> for UpdateType {
         use meilisearch_lib::milli::update::IndexDocumentsMethod::*;
         match other.meta() {
             Update::DocumentAddition { method, .. } => {
-                let number = match other {
+                let (number, skipped) = match other {
                     UpdateStatus::Processed(processed) => match processed.success {
                         UpdateResult::DocumentsAddition(ref addition) => {
-                            Some(addition.nb_documents)
+                            (Some(addition.nb_documents), addition.nb_skipped_documents)
                         }
-                        _ => None,
+                        _ => (None, None),
                     },
-                    _ => None,
+                    _ => (None, None),
                 };
 
                 match method {
-                    ReplaceDocuments => UpdateType::DocumentsAddition { number },
-                    UpdateDocuments => UpdateType::DocumentsPartial { number },
+                    ReplaceDocuments => UpdateType::DocumentsAddition { number, skipped },
+                    UpdateDocuments => UpdateType::DocumentsPartial { number, skipped },
                     _ => unreachable!(),
                 }
             }
@@ -76,6 +115,31 @@ impl From<&UpdateStatus> for UpdateType {
             Update::DeleteDocuments(ids) => UpdateType::DocumentsDeletion {
                 number: Some(ids.len()),
             },
+            Update::DocumentEdition { .. } => {
+                let number = match other {
+                    UpdateStatus::Processed(processed) => match processed.success {
+                        UpdateResult::DocumentEdition { edited } => Some(edited as usize),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+
+                UpdateType::DocumentsEdition { number }
+            }
+            Update::PrimaryKeyChange { primary_key } => {
+                let number = match other {
+                    UpdateStatus::Processed(processed) => match processed.success {
+                        UpdateResult::PrimaryKeyChange { documents } => Some(documents as usize),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+
+                UpdateType::PrimaryKeyChange {
+                    primary_key: primary_key.clone(),
+                    number,
+                }
+            }
         }
     }
 }
@@ -89,6 +153,14 @@ pub struct ProcessedUpdateResult {
     pub duration: f64, // in seconds
     pub enqueued_at: DateTime<Utc>,
     pub processed_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub nb_invalid_documents: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalid_documents: Vec<InvalidDocument>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,6 +185,19 @@ pub struct EnqueuedUpdateResult {
     pub enqueued_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub started_processing_at: Option<DateTime<Utc>>,
+    /// The latest indexing step of a processing update.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<IndexingProgress>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbortedUpdateResult {
+    pub update_id: u64,
+    #[serde(rename = "type")]
+    pub update_type: UpdateType,
+    pub enqueued_at: DateTime<Utc>,
+    pub aborted_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -134,6 +219,10 @@ pub enum UpdateStatusResponse {
         #[serde(flatten)]
         content: ProcessedUpdateResult,
     },
+    Aborted {
+        #[serde(flatten)]
+        content: AbortedUpdateResult,
+    },
 }
 
 impl From<UpdateStatus> for UpdateStatusResponse {
@@ -147,6 +236,7 @@ impl From<UpdateStatus> for UpdateStatusResponse {
                     update_type,
                     enqueued_at: processing.from.enqueued_at,
                     started_processing_at: Some(processing.started_processing_at),
+                    progress: processing.progress,
                 };
                 UpdateStatusResponse::Processing { content }
             }
@@ -156,6 +246,7 @@ impl From<UpdateStatus> for UpdateStatusResponse {
                     update_type,
                     enqueued_at: enqueued.enqueued_at,
                     started_processing_at: None,
+                    progress: None,
                 };
                 UpdateStatusResponse::Enqueued { content }
             }
@@ -168,16 +259,34 @@ impl From<UpdateStatus> for UpdateStatusResponse {
                 // necessary since chrono::duration don't expose a f64 secs method.
                 let duration = Duration::from_millis(duration as u64).as_secs_f64();
 
+                let (nb_invalid_documents, invalid_documents) = match processed.success {
+                    UpdateResult::DocumentsAddition(ref addition) => (
+                        addition.nb_invalid_documents,
+                        addition.invalid_documents.clone(),
+                    ),
+                    _ => (0, Vec::new()),
+                };
+
                 let content = ProcessedUpdateResult {
                     update_id: processed.id(),
                     update_type,
                     duration,
                     enqueued_at: processed.from.from.enqueued_at,
                     processed_at: processed.processed_at,
+                    nb_invalid_documents,
+                    invalid_documents,
                 };
                 UpdateStatusResponse::Processed { content }
             }
-            UpdateStatus::Aborted(_) => unreachable!(),
+            UpdateStatus::Aborted(aborted) => {
+                let content = AbortedUpdateResult {
+                    update_id: aborted.id(),
+                    update_type,
+                    enqueued_at: aborted.from.enqueued_at,
+                    aborted_at: aborted.aborted_at,
+                };
+                UpdateStatusResponse::Aborted { content }
+            }
             UpdateStatus::Failed(failed) => {
                 let duration = failed
                     .failed_at
@@ -211,6 +320,33 @@ pub struct IndexParam {
     index_uid: String,
 }
 
+/// The options of the update registered by a route. The updates with a higher priority are
+/// processed first, and the webhook is notified when the update is finished.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOptionsQuery {
+    #[serde(default)]
+    pub priority: u8,
+    pub webhook: Option<String>,
+}
+
+impl UpdateOptionsQuery {
+    pub fn into_options(self) -> Result<UpdateOptions, MeilisearchHttpError> {
+        if let Some(ref webhook) = self.webhook {
+            #[cfg(feature = "analytics")]
+            reqwest::Url::parse(webhook)
+                .map_err(|_| MeilisearchHttpError::InvalidWebhookUrl(webhook.clone()))?;
+            #[cfg(not(feature = "analytics"))]
+            return Err(MeilisearchHttpError::WebhooksDisabled(webhook.clone()));
+        }
+
+        Ok(UpdateOptions {
+            priority: self.priority,
+            webhook: self.webhook,
+        })
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IndexUpdateResponse {
@@ -334,9 +470,22 @@ mod test {
             indexes::delete_index,
             indexes::update_index,
             indexes::get_index,
+            indexes::clone_index,
+            indexes::change_primary_key,
 
             dump::create_dump,
 
+            tasks::get_tasks,
+            tasks::cancel_tasks,
+            tasks::prune_tasks,
+            tasks::stream_tasks,
+            swap_indexes::swap_indexes,
+
+            aliases::list_aliases,
+            aliases::get_alias,
+            aliases::put_alias,
+            aliases::delete_alias,
+
             indexes::settings::filterable_attributes::get,
             indexes::settings::displayed_attributes::get,
             indexes::settings::searchable_attributes::get,
@@ -364,9 +513,11 @@ mod test {
             indexes::documents::update_documents,
             indexes::documents::add_documents,
             indexes::documents::delete_document,
+            indexes::documents::edit_documents,
 
             indexes::updates::get_all_updates_status,
             indexes::updates::get_update_status,
+            indexes::updates::wait_update_status,
         }
         Admin => { list_keys, }
     }
@@ -0,0 +1,33 @@
+use actix_web::{web, HttpResponse};
+use log::debug;
+use meilisearch_lib::MeiliSearch;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ResponseError;
+use crate::extractors::authentication::{policies::*, GuardedData};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("").route(web::post().to(swap_indexes)));
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SwapIndexesPayload {
+    indexes: (String, String),
+}
+
+/// Swaps the uids of each pair of indexes, all at once. The pending updates of the swapped
+/// indexes are processed before the swap.
+pub async fn swap_indexes(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    body: web::Json<Vec<SwapIndexesPayload>>,
+) -> Result<HttpResponse, ResponseError> {
+    debug!("called with params: {:?}", body);
+    let swaps = body.into_inner();
+    let pairs = swaps.iter().map(|swap| swap.indexes.clone()).collect();
+
+    meilisearch.swap_indexes(pairs).await?;
+
+    debug!("returns: {:?}", swaps);
+    Ok(HttpResponse::Ok().json(swaps))
+}
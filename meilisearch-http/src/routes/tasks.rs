@@ -0,0 +1,269 @@
+use std::time::Duration;
+
+use actix_web::web::Bytes;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use log::debug;
+use meilisearch_lib::index_controller::updates::status::UpdateStatus;
+use meilisearch_lib::index_controller::updates::RetentionPolicy;
+use meilisearch_lib::index_controller::{Task, TaskEvent};
+use meilisearch_lib::MeiliSearch;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MeilisearchHttpError, ResponseError};
+use crate::extractors::authentication::{policies::*, GuardedData};
+
+use super::{UpdateStatusResponse, UpdateType};
+
+const DEFAULT_TASKS_LIMIT: usize = 20;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("").route(web::get().to(get_tasks)))
+        .service(web::resource("/cancel").route(web::post().to(cancel_tasks)))
+        .service(web::resource("/prune").route(web::post().to(prune_tasks)))
+        .service(web::resource("/stream").route(web::get().to(stream_tasks)));
+}
+
+/// The list filters are comma separated values, a task matches a filter if it matches any of
+/// its values.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TasksQuery {
+    status: Option<String>,
+    #[serde(rename = "type")]
+    update_type: Option<String>,
+    index_uid: Option<String>,
+    enqueued_after: Option<DateTime<Utc>>,
+    enqueued_before: Option<DateTime<Utc>>,
+    processed_after: Option<DateTime<Utc>>,
+    processed_before: Option<DateTime<Utc>>,
+    /// The uid of the most recent task to return.
+    from: Option<u64>,
+    limit: Option<usize>,
+}
+
+/// Returns `true` if there is no comma separated `list`, or if `value` is one of its values.
+fn in_list(list: &Option<String>, value: &str) -> bool {
+    list.as_ref()
+        .map_or(true, |list| list.split(',').any(|v| v.trim() == value))
+}
+
+impl TasksQuery {
+    fn matches(&self, task: &Task) -> bool {
+        let enqueued_at = task.update.enqueued().enqueued_at;
+        let processed_at = task.update.finished_at();
+
+        in_list(&self.status, status_name(&task.update))
+            && in_list(&self.update_type, UpdateType::from(&task.update).name())
+            && in_list(&self.index_uid, &task.index_uid)
+            && self.enqueued_after.map_or(true, |date| enqueued_at > date)
+            && self.enqueued_before.map_or(true, |date| enqueued_at < date)
+            && self
+                .processed_after
+                .map_or(true, |date| processed_at.map_or(false, |at| at > date))
+            && self
+                .processed_before
+                .map_or(true, |date| processed_at.map_or(false, |at| at < date))
+    }
+}
+
+fn status_name(update: &UpdateStatus) -> &'static str {
+    match update {
+        UpdateStatus::Enqueued(_) => "enqueued",
+        UpdateStatus::Processing(_) => "processing",
+        UpdateStatus::Processed(_) => "processed",
+        UpdateStatus::Aborted(_) => "aborted",
+        UpdateStatus::Failed(_) => "failed",
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskResponse {
+    uid: u64,
+    index_uid: String,
+    #[serde(flatten)]
+    update: UpdateStatusResponse,
+}
+
+impl From<Task> for TaskResponse {
+    fn from(task: Task) -> Self {
+        Self {
+            uid: task.uid,
+            index_uid: task.index_uid,
+            update: task.update.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TasksResponse {
+    results: Vec<TaskResponse>,
+    limit: usize,
+    from: Option<u64>,
+    /// The uid to pass as `from` to get the next page, if there is one.
+    next: Option<u64>,
+}
+
+pub async fn get_tasks(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    params: web::Query<TasksQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    debug!("called with params: {:?}", params);
+    let params = params.into_inner();
+    let limit = params.limit.unwrap_or(DEFAULT_TASKS_LIMIT);
+
+    let (tasks, next) = meilisearch
+        .list_tasks(params.from, limit, |task| params.matches(task))
+        .await?;
+    let results = tasks.into_iter().map(TaskResponse::from).collect();
+
+    let response = TasksResponse {
+        results,
+        limit,
+        from: params.from,
+        next,
+    };
+
+    debug!("returns: {:?}", response);
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Selects the enqueued tasks to cancel, every enqueued task is selected when no filter is given.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct CancelTasksQuery {
+    #[serde(rename = "type")]
+    update_type: Option<String>,
+    index_uid: Option<String>,
+    uids: Option<String>,
+}
+
+impl CancelTasksQuery {
+    fn matches(&self, task: &Task) -> bool {
+        matches!(task.update, UpdateStatus::Enqueued(_))
+            && in_list(&self.update_type, UpdateType::from(&task.update).name())
+            && in_list(&self.index_uid, &task.index_uid)
+            && in_list(&self.uids, &task.uid.to_string())
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelTasksResponse {
+    results: Vec<TaskResponse>,
+}
+
+pub async fn cancel_tasks(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    params: web::Query<CancelTasksQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    debug!("called with params: {:?}", params);
+    let uids = meilisearch
+        .list_enqueued_tasks(|task| params.matches(task))
+        .await?
+        .into_iter()
+        .map(|task| task.uid)
+        .collect();
+
+    let results = meilisearch
+        .cancel_tasks(uids)
+        .await?
+        .into_iter()
+        .map(TaskResponse::from)
+        .collect();
+
+    let response = CancelTasksResponse { results };
+
+    debug!("returns: {:?}", response);
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Selects the finished tasks to delete. At least one of `maxAge` or `maxCount` must be given.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PruneTasksQuery {
+    /// The age, in seconds, after which the finished tasks are deleted.
+    max_age: Option<u64>,
+    /// The number of finished tasks kept for each index.
+    max_count: Option<usize>,
+    index_uid: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PruneTasksResponse {
+    deleted_tasks: usize,
+}
+
+pub async fn prune_tasks(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    params: web::Query<PruneTasksQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    debug!("called with params: {:?}", params);
+    let params = params.into_inner();
+    if params.max_age.is_none() && params.max_count.is_none() {
+        return Err(MeilisearchHttpError::MissingRetentionPolicy.into());
+    }
+    let policy = RetentionPolicy {
+        max_age: params.max_age.map(Duration::from_secs),
+        max_count: params.max_count,
+    };
+    let index_uids = params
+        .index_uid
+        .map(|uids| uids.split(',').map(|uid| uid.trim().to_string()).collect());
+
+    let deleted_tasks = meilisearch.prune_tasks(policy, index_uids).await?;
+
+    let response = PruneTasksResponse { deleted_tasks };
+
+    debug!("returns: {:?}", response);
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct StreamTasksQuery {
+    index_uid: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LaggedEvent {
+    missed_events: u64,
+}
+
+/// Streams the status changes of the tasks as server-sent events. Each event is named after the
+/// new status of the task, and its data is the task. A `lagged` event is sent when events were
+/// missed because the client read the stream too slowly, the tasks must then be listed again.
+pub async fn stream_tasks(
+    meilisearch: GuardedData<Private, MeiliSearch>,
+    params: web::Query<StreamTasksQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    debug!("called with params: {:?}", params);
+    let tasks = match params.into_inner().index_uid {
+        Some(index_uid) => meilisearch.index_task_events(index_uid).await?,
+        None => meilisearch.task_events(),
+    };
+    let events = tasks.map(|event| {
+        let (event, data) = match event {
+            TaskEvent::Task(task) => (
+                status_name(&task.update),
+                serde_json::to_string(&TaskResponse::from(task)),
+            ),
+            TaskEvent::Lagged(missed_events) => (
+                "lagged",
+                serde_json::to_string(&LaggedEvent { missed_events }),
+            ),
+        };
+        data.map(|data| Bytes::from(format!("event: {}\ndata: {}\n\n", event, data)))
+            .map_err(actix_web::error::ErrorInternalServerError)
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events))
+}
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use log::{debug, error, warn};
+use meilisearch_lib::index_controller::{Task, TaskEvent};
+use meilisearch_lib::MeiliSearch;
+use serde::Serialize;
+use tokio::time::sleep;
+
+use crate::routes::UpdateStatusResponse;
+
+/// Delay before the first retry of a failed webhook call, doubled after each retry.
+const WEBHOOK_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Maximum delay between two retries of a failed webhook call.
+const WEBHOOK_MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+/// Duration after which a webhook call that kept failing is given up.
+const WEBHOOK_DELIVERY_DEADLINE: Duration = Duration::from_secs(24 * 60 * 60);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The body posted to a webhook.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebhookPayload {
+    index_uid: String,
+    #[serde(flatten)]
+    update: UpdateStatusResponse,
+}
+
+/// Posts the status of every finished task to its webhook, or to the `default_webhook` if the
+/// task was registered without one. The tasks are read from the webhook outbox of the update
+/// store, once on startup and then each time a task finishes, so that no task is missed across
+/// restarts or when the task events lag.
+pub async fn webhook_sender(meilisearch: MeiliSearch, default_webhook: Option<String>) {
+    let client = reqwest::Client::new();
+    // The tasks whose webhook is being notified, they stay in the outbox until then.
+    let in_flight = Arc::new(Mutex::new(HashSet::new()));
+    let mut events = meilisearch.task_events();
+
+    loop {
+        match meilisearch.webhook_outbox().await {
+            Ok(tasks) => {
+                for task in tasks {
+                    if !in_flight.lock().unwrap().insert(task.uid) {
+                        continue;
+                    }
+
+                    let webhook = task.update.enqueued().webhook.clone();
+                    let url = webhook.or_else(|| default_webhook.clone());
+                    tokio::task::spawn(notify(
+                        meilisearch.clone(),
+                        client.clone(),
+                        url,
+                        task,
+                        in_flight.clone(),
+                    ));
+                }
+            }
+            Err(e) => warn!("could not read the webhook outbox: {}", e),
+        }
+
+        // Waits for a task to finish, or for missed events, before reading the outbox again.
+        loop {
+            match events.next().await {
+                Some(TaskEvent::Task(task)) if task.update.finished_at().is_some() => break,
+                Some(TaskEvent::Lagged(_)) => break,
+                Some(TaskEvent::Task(_)) => continue,
+                None => return,
+            }
+        }
+    }
+}
+
+/// Posts `task` to `url`, and retries with an exponential backoff until it succeeds. The task
+/// stays in the outbox meanwhile, so the retries resume after a restart. The task is only
+/// dead-lettered, logging its payload, if the calls kept failing for
+/// `WEBHOOK_DELIVERY_DEADLINE`.
+async fn notify(
+    meilisearch: MeiliSearch,
+    client: reqwest::Client,
+    url: Option<String>,
+    task: Task,
+    in_flight: Arc<Mutex<HashSet<u64>>>,
+) {
+    let uid = task.uid;
+    if let Some(url) = url {
+        let payload = WebhookPayload {
+            index_uid: task.index_uid,
+            update: UpdateStatusResponse::from(task.update),
+        };
+        if let Err(e) = post_with_retries(&client, &url, &payload).await {
+            error!(
+                "dead-lettered the notification of task {} to webhook {}: {}. Payload: {}",
+                uid,
+                url,
+                e,
+                serde_json::to_string(&payload).unwrap_or_default()
+            );
+        }
+    }
+
+    if let Err(e) = meilisearch.acknowledge_webhook(uid).await {
+        warn!(
+            "could not remove task {} from the webhook outbox: {}",
+            uid, e
+        );
+    }
+    in_flight.lock().unwrap().remove(&uid);
+}
+
+/// Posts `payload` to `url` until it succeeds, or until `WEBHOOK_DELIVERY_DEADLINE` is reached,
+/// in which case the last error is returned.
+async fn post_with_retries(
+    client: &reqwest::Client,
+    url: &str,
+    payload: &WebhookPayload,
+) -> Result<(), reqwest::Error> {
+    let deadline = Instant::now() + WEBHOOK_DELIVERY_DEADLINE;
+    let mut backoff = WEBHOOK_INITIAL_BACKOFF;
+    loop {
+        let response = client
+            .post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        match response {
+            Ok(_) => {
+                debug!("notified {} of the end of an update", url);
+                return Ok(());
+            }
+            Err(e) if Instant::now() + backoff < deadline => {
+                warn!(
+                    "unsuccessful call to webhook {}, retrying in {:?}: {}",
+                    url, backoff, e
+                );
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(WEBHOOK_MAX_BACKOFF);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
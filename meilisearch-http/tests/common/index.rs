@@ -168,6 +168,11 @@ impl Index<'_> {
             .await
     }
 
+    pub async fn edit_documents(&self, body: Value) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/documents/edit", self.uid);
+        self.service.post(url, body).await
+    }
+
     pub async fn settings(&self) -> (Value, StatusCode) {
         let url = format!("/indexes/{}/settings", self.uid);
         self.service.get(url).await
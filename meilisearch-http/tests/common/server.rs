@@ -78,6 +78,33 @@ impl Server {
     pub async fn stats(&self) -> (Value, StatusCode) {
         self.service.get("/stats").await
     }
+
+    pub async fn swap_indexes(&self, swaps: Value) -> (Value, StatusCode) {
+        self.service.post("/swap-indexes", swaps).await
+    }
+
+    pub async fn put_alias(&self, alias: &str, index_uid: &str) -> (Value, StatusCode) {
+        let url = format!("/aliases/{}", alias);
+        self.service
+            .put(url, serde_json::json!({ "indexUid": index_uid }))
+            .await
+    }
+
+    pub async fn get_alias(&self, alias: &str) -> (Value, StatusCode) {
+        self.service.get(format!("/aliases/{}", alias)).await
+    }
+
+    pub async fn list_aliases(&self) -> (Value, StatusCode) {
+        self.service.get("/aliases").await
+    }
+
+    pub async fn delete_alias(&self, alias: &str) -> (Value, StatusCode) {
+        self.service.delete(format!("/aliases/{}", alias)).await
+    }
+
+    pub async fn tasks(&self, query: &str) -> (Value, StatusCode) {
+        self.service.get(format!("/tasks?{}", query)).await
+    }
 }
 
 pub fn default_settings(dir: impl AsRef<Path>) -> Opt {
@@ -90,6 +117,7 @@ pub fn default_settings(dir: impl AsRef<Path>) -> Opt {
         #[cfg(all(not(debug_assertions), feature = "analytics"))]
         no_analytics: true,
         max_index_size: Byte::from_unit(4.0, ByteUnit::GiB).unwrap(),
+        max_open_indexes: None,
         max_udb_size: Byte::from_unit(4.0, ByteUnit::GiB).unwrap(),
         http_payload_size_limit: Byte::from_unit(10.0, ByteUnit::MiB).unwrap(),
         ssl_cert_path: None,
@@ -106,6 +134,10 @@ pub fn default_settings(dir: impl AsRef<Path>) -> Opt {
         schedule_snapshot: false,
         snapshot_interval_sec: 0,
         import_dump: None,
+        update_workers: 1,
+        updates_retention_max_age_sec: None,
+        updates_retention_max_count: None,
+        update_webhook_url: None,
         indexer_options: IndexerOpts {
             // memory has to be unlimited because several meilisearch are running in test context.
             max_memory: MaxMemory::unlimited(),
@@ -1,4 +1,6 @@
+use actix_web::web::Bytes;
 use actix_web::{http::StatusCode, test};
+use futures::{Stream, StreamExt};
 use meilisearch_lib::MeiliSearch;
 use serde_json::Value;
 
@@ -58,6 +60,22 @@ impl Service {
         (response, status_code)
     }
 
+    /// Send a test get request to a route streaming its response, and returns the stream of the
+    /// response body chunks.
+    pub async fn get_stream(
+        &self,
+        url: impl AsRef<str>,
+    ) -> (impl Stream<Item = Bytes> + Unpin, StatusCode) {
+        let app = test::init_service(create_app!(&self.meilisearch, true, &self.options)).await;
+
+        let req = test::TestRequest::get().uri(url.as_ref()).to_request();
+        let mut res = test::call_service(&app, req).await;
+        let status_code = res.status();
+
+        let body = res.take_body().map(|chunk| chunk.unwrap());
+        (body, status_code)
+    }
+
     pub async fn put(&self, url: impl AsRef<str>, body: Value) -> (Value, StatusCode) {
         let app = test::init_service(create_app!(&self.meilisearch, true, &self.options)).await;
 
@@ -442,3 +442,250 @@ async fn update_documents_bad_primary_key() {
     assert_eq!(code, 200);
     assert_eq!(response["status"], "failed");
 }
+
+#[actix_rt::test]
+async fn add_versioned_documents_skips_outdated_documents() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+
+    let url = "/indexes/test/documents?versionField=_version";
+    let documents = json!([{ "id": 1, "_version": 2, "content": "new" }]);
+    index.service.post(url, documents).await;
+    index.wait_update_id(0).await;
+
+    let documents = json!([
+        { "id": 1, "_version": 1, "content": "old" },
+        { "id": 2, "_version": 1, "content": "other" }
+    ]);
+    index.service.post(url, documents).await;
+    let response = index.wait_update_id(1).await;
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["type"]["number"], 1);
+    assert_eq!(response["type"]["skipped"], 1);
+
+    let (response, code) = index.get_document(1, None).await;
+    assert_eq!(code, 200);
+    assert_eq!(response["content"], "new");
+}
+
+#[actix_rt::test]
+async fn add_versioned_documents_keeps_latest_duplicate_of_payload() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+
+    let url = "/indexes/test/documents?versionField=_version";
+    let documents = json!([
+        { "id": 1, "_version": 3, "content": "new" },
+        { "id": 1, "_version": 2, "content": "old" },
+        { "id": 2, "_version": 1, "content": "first" },
+        { "id": 2, "_version": 1, "content": "last" }
+    ]);
+    index.service.post(url, documents).await;
+    let response = index.wait_update_id(0).await;
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["type"]["number"], 2);
+    assert_eq!(response["type"]["skipped"], 2);
+
+    let (response, code) = index.get_document(1, None).await;
+    assert_eq!(code, 200);
+    assert_eq!(response["content"], "new");
+    let (response, code) = index.get_document(2, None).await;
+    assert_eq!(code, 200);
+    assert_eq!(response["content"], "last");
+}
+
+#[actix_rt::test]
+async fn add_versioned_documents_invalid_version() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+
+    let url = "/indexes/test/documents?versionField=_version";
+    let documents = json!([{ "id": 1, "_version": "two" }]);
+    index.service.post(url, documents).await;
+    let response = index.wait_update_id(0).await;
+    assert_eq!(response["status"], "failed");
+    assert_eq!(response["errorCode"], "invalid_document_version");
+}
+
+#[actix_rt::test]
+async fn add_documents_dry_run() {
+    let server = Server::new().await;
+    let index = server.index("test");
+
+    let documents = json!([
+        { "id": 1, "_geo": { "lat": 48.85, "lng": 2.35 } },
+        { "id": "invalid id" },
+        { "content": "no id" },
+        { "id": 4, "_geo": { "lat": 48.85 } }
+    ]);
+    let (response, code) = index
+        .service
+        .post("/indexes/test/documents?dryRun=true", documents)
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["primaryKey"], "id");
+    assert_eq!(response["nbDocuments"], 4);
+    assert_eq!(response["nbInvalidDocuments"], 3);
+
+    let errors = response["errors"].as_array().unwrap();
+    assert_eq!(errors[0]["position"], 1);
+    assert_eq!(errors[0]["errorCode"], "invalid_document_id");
+    assert_eq!(errors[1]["position"], 2);
+    assert_eq!(errors[1]["errorCode"], "missing_document_id");
+    assert_eq!(errors[2]["position"], 3);
+    assert_eq!(errors[2]["documentId"], 4);
+    assert_eq!(errors[2]["errorCode"], "invalid_geo_field");
+
+    // nothing was registered
+    let (_response, code) = index.get().await;
+    assert_eq!(code, 404);
+}
+
+#[actix_rt::test]
+async fn add_documents_dry_run_uses_index_primary_key() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("docid")).await;
+
+    let documents = json!([{ "id": 1, "docid": 1 }]);
+    let (response, code) = index
+        .service
+        .put(
+            "/indexes/test/documents?dryRun=true&primaryKey=id",
+            documents,
+        )
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["primaryKey"], "docid");
+    assert_eq!(response["nbInvalidDocuments"], 0);
+
+    let (response, code) = index.list_updates().await;
+    assert_eq!(code, 200);
+    assert!(response.as_array().unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn add_documents_lenient_skips_invalid_documents() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+
+    let documents = json!([
+        { "id": 1, "content": "valid" },
+        { "id": "invalid id", "content": "invalid" },
+        { "content": "missing id" },
+        { "id": 4, "_geo": { "lat": 48.85 } }
+    ]);
+    let (response, code) = index
+        .service
+        .post("/indexes/test/documents?lenient=true", documents)
+        .await;
+    assert_eq!(code, 202, "{}", response);
+
+    let response = index.wait_update_id(0).await;
+    assert_eq!(response["status"], "processed", "{}", response);
+    assert_eq!(response["type"]["number"], 1);
+
+    let invalid_documents = response["invalidDocuments"].as_array().unwrap();
+    assert_eq!(invalid_documents.len(), 3);
+    assert_eq!(invalid_documents[0]["position"], 1);
+    assert_eq!(invalid_documents[0]["documentId"], "invalid id");
+    assert_eq!(invalid_documents[0]["errorCode"], "invalid_document_id");
+    assert_eq!(invalid_documents[1]["position"], 2);
+    assert_eq!(invalid_documents[1]["errorCode"], "missing_document_id");
+    assert_eq!(invalid_documents[2]["documentId"], 4);
+    assert_eq!(invalid_documents[2]["errorCode"], "invalid_geo_field");
+
+    let (response, code) = index.get_document(1, None).await;
+    assert_eq!(code, 200);
+    assert_eq!(response["content"], "valid");
+}
+
+#[actix_rt::test]
+async fn add_documents_lenient_uses_given_primary_key_and_caps_reported_documents() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(None).await;
+
+    // The inferred primary key would be `id`, for which every document is valid.
+    let documents: Vec<_> = (0..30)
+        .map(|id| json!({ "id": id, "docid": format!("invalid id {}", id) }))
+        .chain(std::iter::once(json!({ "id": 30, "docid": "valid" })))
+        .collect();
+    let (response, code) = index
+        .service
+        .post(
+            "/indexes/test/documents?primaryKey=docid&lenient=true",
+            json!(documents),
+        )
+        .await;
+    assert_eq!(code, 202, "{}", response);
+
+    let response = index.wait_update_id(0).await;
+    assert_eq!(response["status"], "processed", "{}", response);
+    assert_eq!(response["nbInvalidDocuments"], 30);
+    let invalid_documents = response["invalidDocuments"].as_array().unwrap();
+    assert_eq!(invalid_documents.len(), 20);
+    assert_eq!(invalid_documents[0]["documentId"], "invalid id 0");
+
+    let (response, code) = index.service.get("/indexes/test/documents/valid").await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["id"], 30);
+}
+
+#[actix_rt::test]
+async fn consecutive_document_additions_keep_their_own_status() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+
+    for id in 0..10 {
+        let documents = json!([
+            { "id": id * 2, "content": "foo" },
+            { "id": id * 2 + 1, "content": "bar" }
+        ]);
+        let (response, code) = index.add_documents(documents, None).await;
+        assert_eq!(code, 202, "{}", response);
+    }
+
+    for update_id in 0..10 {
+        let response = index.wait_update_id(update_id).await;
+        assert_eq!(response["status"], "processed", "{}", response);
+        assert_eq!(response["type"]["number"], 2);
+    }
+
+    let (response, code) = index.get_all_documents(Default::default()).await;
+    assert_eq!(code, 200);
+    assert_eq!(response.as_array().unwrap().len(), 20);
+}
+
+#[actix_rt::test]
+async fn invalid_document_addition_does_not_fail_consecutive_additions() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+
+    index
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+    index
+        .add_documents(json!([{ "id": "invalid id", "content": "bar" }]), None)
+        .await;
+    index
+        .add_documents(json!([{ "id": 3, "content": "baz" }]), None)
+        .await;
+
+    let response = index.wait_update_id(0).await;
+    assert_eq!(response["status"], "processed", "{}", response);
+    let response = index.wait_update_id(1).await;
+    assert_eq!(response["status"], "failed", "{}", response);
+    let response = index.wait_update_id(2).await;
+    assert_eq!(response["status"], "processed", "{}", response);
+
+    let (response, code) = index.get_all_documents(Default::default()).await;
+    assert_eq!(code, 200);
+    assert_eq!(response.as_array().unwrap().len(), 2);
+}
@@ -0,0 +1,120 @@
+use serde_json::json;
+
+use crate::common::Server;
+
+#[actix_rt::test]
+async fn edit_documents_unexisting_index() {
+    let server = Server::new().await;
+    let (_response, code) = server
+        .index("test")
+        .edit_documents(json!({ "function": "doc.title = \"foo\";" }))
+        .await;
+    assert_eq!(code, 404);
+}
+
+#[actix_rt::test]
+async fn edit_all_documents() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(
+            json!([
+                { "id": 0, "full_name": "Bilbo Baggins" },
+                { "id": 1, "full_name": "Frodo Baggins" },
+            ]),
+            None,
+        )
+        .await;
+    index.wait_update_id(0).await;
+
+    let function = r#"
+        let names = doc.full_name.split(" ");
+        doc.first_name = names[0];
+        doc.last_name = names[1];
+        doc.remove("full_name");
+    "#;
+    let (response, code) = index.edit_documents(json!({ "function": function })).await;
+    assert_eq!(code, 202, "{}", response);
+
+    let update = index.wait_update_id(1).await;
+    assert_eq!(update["status"], "processed");
+    assert_eq!(update["type"]["name"], "DocumentsEdition");
+    assert_eq!(update["type"]["number"], 2);
+
+    let (response, code) = index.get_document(1, None).await;
+    assert_eq!(code, 200);
+    assert_eq!(
+        response,
+        json!({ "id": 1, "first_name": "Frodo", "last_name": "Baggins" })
+    );
+}
+
+#[actix_rt::test]
+async fn edit_filtered_documents() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .update_settings(json!({ "filterableAttributes": ["color"] }))
+        .await;
+    index
+        .add_documents(
+            json!([
+                { "id": 0, "color": "red", "count": 1 },
+                { "id": 1, "color": "blue", "count": 1 },
+            ]),
+            None,
+        )
+        .await;
+    index.wait_update_id(1).await;
+
+    let (_response, code) = index
+        .edit_documents(json!({ "function": "doc.count += 1;", "filter": "color = red" }))
+        .await;
+    assert_eq!(code, 202);
+
+    let update = index.wait_update_id(2).await;
+    assert_eq!(update["status"], "processed");
+    assert_eq!(update["type"]["number"], 1);
+
+    let (response, _code) = index.get_document(0, None).await;
+    assert_eq!(response["count"], 2);
+    let (response, _code) = index.get_document(1, None).await;
+    assert_eq!(response["count"], 1);
+}
+
+#[actix_rt::test]
+async fn edit_documents_invalid_function() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.add_documents(json!([{ "id": 0 }]), None).await;
+    index.wait_update_id(0).await;
+
+    let (_response, code) = index
+        .edit_documents(json!({ "function": "doc.title = ;" }))
+        .await;
+    assert_eq!(code, 202);
+
+    let update = index.wait_update_id(1).await;
+    assert_eq!(update["status"], "failed");
+    assert_eq!(update["errorCode"], "invalid_document_edition");
+}
+
+#[actix_rt::test]
+async fn edit_documents_primary_key() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.add_documents(json!([{ "id": 0 }]), None).await;
+    index.wait_update_id(0).await;
+
+    let (_response, code) = index
+        .edit_documents(json!({ "function": "doc.id = 42;" }))
+        .await;
+    assert_eq!(code, 202);
+
+    let update = index.wait_update_id(1).await;
+    assert_eq!(update["status"], "failed");
+    assert_eq!(update["errorCode"], "invalid_document_edition");
+
+    let (_response, code) = index.get_document(0, None).await;
+    assert_eq!(code, 200);
+}
@@ -0,0 +1,144 @@
+use serde_json::json;
+
+use crate::common::Server;
+
+#[actix_rt::test]
+async fn search_through_alias() {
+    let server = Server::new().await;
+    let index = server.index("products_2026_10");
+    index
+        .add_documents(json!([{ "id": 1, "title": "hello" }]), None)
+        .await;
+    index.wait_update_id(0).await;
+
+    let (response, code) = server.put_alias("products", "products_2026_10").await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(
+        response,
+        json!({ "alias": "products", "indexUid": "products_2026_10" })
+    );
+
+    let alias = server.index("products");
+    let (response, code) = alias.search_post(json!({ "q": "hello" })).await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["hits"][0]["id"], 1);
+
+    let (response, code) = alias.get_document(1, None).await;
+    assert_eq!(code, 200, "{}", response);
+}
+
+#[actix_rt::test]
+async fn aliases_are_read_only() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(json!([{ "id": 1, "title": "hello" }]), None)
+        .await;
+    index.wait_update_id(0).await;
+    server.put_alias("alias", "test").await;
+
+    // The writes don't go through the alias, and they can't create an index named like it.
+    let alias = server.index("alias");
+    let (response, code) = alias
+        .add_documents(json!([{ "id": 2, "title": "world" }]), None)
+        .await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "index_already_exists");
+    let (response, code) = alias.update_settings(json!({ "rankingRules": [] })).await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "index_already_exists");
+    let (_response, code) = alias.delete().await;
+    assert_eq!(code, 404);
+
+    let (stats, code) = alias.stats().await;
+    assert_eq!(code, 200, "{}", stats);
+    assert_eq!(stats["numberOfDocuments"], 1);
+}
+
+#[actix_rt::test]
+async fn update_alias() {
+    let server = Server::new().await;
+    server.index("v1").create(None).await;
+    server.index("v2").create(None).await;
+
+    server.put_alias("current", "v1").await;
+    let (response, code) = server.put_alias("current", "v2").await;
+    assert_eq!(code, 200, "{}", response);
+
+    let (response, code) = server.get_alias("current").await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["indexUid"], "v2");
+
+    let (response, code) = server.list_aliases().await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response, json!([{ "alias": "current", "indexUid": "v2" }]));
+}
+
+#[actix_rt::test]
+async fn delete_alias() {
+    let server = Server::new().await;
+    server.index("test").create(None).await;
+    server.put_alias("alias", "test").await;
+
+    let (_response, code) = server.delete_alias("alias").await;
+    assert_eq!(code, 204);
+
+    let (response, code) = server.get_alias("alias").await;
+    assert_eq!(code, 404, "{}", response);
+    assert_eq!(response["errorCode"], "alias_not_found");
+
+    let (_response, code) = server.index("alias").get().await;
+    assert_eq!(code, 404);
+
+    let (response, code) = server.delete_alias("alias").await;
+    assert_eq!(code, 404, "{}", response);
+}
+
+#[actix_rt::test]
+async fn alias_to_unexisting_index() {
+    let server = Server::new().await;
+
+    let (response, code) = server.put_alias("alias", "test").await;
+    assert_eq!(code, 404, "{}", response);
+    assert_eq!(response["errorCode"], "index_not_found");
+}
+
+#[actix_rt::test]
+async fn invalid_alias() {
+    let server = Server::new().await;
+    server.index("test").create(None).await;
+    server.index("other").create(None).await;
+
+    let (response, code) = server.put_alias("in valid", "test").await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "invalid_alias");
+
+    // An alias can't shadow an index.
+    let (response, code) = server.put_alias("other", "test").await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "invalid_alias");
+
+    // And an index can't be created with the name of an alias.
+    server.put_alias("alias", "test").await;
+    let (response, code) = server.index("alias").create(None).await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "index_already_exists");
+}
+
+#[actix_rt::test]
+async fn aliases_follow_renamed_and_deleted_indexes() {
+    let server = Server::new().await;
+    server.index("test").create(None).await;
+    server.put_alias("alias", "test").await;
+
+    server
+        .service
+        .put("/indexes/test", json!({ "uid": "renamed" }))
+        .await;
+    let (response, _code) = server.get_alias("alias").await;
+    assert_eq!(response["indexUid"], "renamed");
+
+    server.index("renamed").delete().await;
+    let (_response, code) = server.get_alias("alias").await;
+    assert_eq!(code, 404);
+}
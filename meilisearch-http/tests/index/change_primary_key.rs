@@ -0,0 +1,146 @@
+use serde_json::json;
+
+use crate::common::Server;
+
+#[actix_rt::test]
+async fn change_primary_key_of_non_empty_index() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(
+            json!([
+                { "id": 1, "sku": 100, "title": "foo" },
+                { "id": 2, "sku": 101, "title": "bar" },
+            ]),
+            Some("id"),
+        )
+        .await;
+    index.wait_update_id(0).await;
+
+    let (response, code) = index
+        .service
+        .put("/indexes/test/primary-key", json!({ "primaryKey": "sku" }))
+        .await;
+    assert_eq!(code, 202, "{}", response);
+    assert_eq!(response["updateId"], 1);
+
+    let update = index.wait_update_id(1).await;
+    assert_eq!(update["status"], "processed", "{}", update);
+    assert_eq!(update["type"]["name"], "PrimaryKeyChange");
+    assert_eq!(update["type"]["primaryKey"], "sku");
+    assert_eq!(update["type"]["number"], 2);
+
+    let (response, _code) = index.get().await;
+    assert_eq!(response["primaryKey"], "sku");
+
+    let (response, code) = index.get_document(101, None).await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["title"], "bar");
+
+    let (_response, code) = index.get_document(1, None).await;
+    assert_eq!(code, 404);
+}
+
+#[actix_rt::test]
+async fn change_primary_key_to_missing_field() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(
+            json!([
+                { "id": 1, "sku": 100 },
+                { "id": 2 },
+            ]),
+            Some("id"),
+        )
+        .await;
+    index.wait_update_id(0).await;
+
+    index
+        .service
+        .put("/indexes/test/primary-key", json!({ "primaryKey": "sku" }))
+        .await;
+    let update = index.wait_update_id(1).await;
+    assert_eq!(update["status"], "failed", "{}", update);
+    assert_eq!(update["errorCode"], "invalid_primary_key_change");
+    assert!(update["message"].as_str().unwrap().contains("(`2`)"));
+
+    // The index is left untouched.
+    let (response, _code) = index.get().await;
+    assert_eq!(response["primaryKey"], "id");
+    let (_response, code) = index.get_document(2, None).await;
+    assert_eq!(code, 200);
+}
+
+#[actix_rt::test]
+async fn change_primary_key_to_invalid_document_ids() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(
+            json!([
+                { "id": 1, "sku": "abc-1" },
+                { "id": 2, "sku": true },
+                { "id": 3, "sku": 1.5 },
+                { "id": 4, "sku": "with space" },
+            ]),
+            Some("id"),
+        )
+        .await;
+    index.wait_update_id(0).await;
+
+    index
+        .service
+        .put("/indexes/test/primary-key", json!({ "primaryKey": "sku" }))
+        .await;
+    let update = index.wait_update_id(1).await;
+    assert_eq!(update["status"], "failed", "{}", update);
+    assert_eq!(update["errorCode"], "invalid_primary_key_change");
+    let message = update["message"].as_str().unwrap();
+    assert!(message.contains("3 documents have a value that isn't a valid document id"));
+    assert!(message.contains("(`2`, `3`, `4`)"), "{}", message);
+
+    let (response, _code) = index.get().await;
+    assert_eq!(response["primaryKey"], "id");
+}
+
+#[actix_rt::test]
+async fn change_primary_key_to_non_unique_field() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(
+            json!([
+                { "id": 1, "sku": 100 },
+                { "id": 2, "sku": 100 },
+                { "id": 3, "sku": 101 },
+            ]),
+            Some("id"),
+        )
+        .await;
+    index.wait_update_id(0).await;
+
+    index
+        .service
+        .put("/indexes/test/primary-key", json!({ "primaryKey": "sku" }))
+        .await;
+    let update = index.wait_update_id(1).await;
+    assert_eq!(update["status"], "failed", "{}", update);
+    assert_eq!(update["errorCode"], "invalid_primary_key_change");
+    assert!(update["message"].as_str().unwrap().contains("(`100`)"));
+
+    let (response, _code) = index.get().await;
+    assert_eq!(response["primaryKey"], "id");
+}
+
+#[actix_rt::test]
+async fn change_primary_key_of_unexisting_index() {
+    let server = Server::new().await;
+
+    let (response, code) = server
+        .service
+        .put("/indexes/test/primary-key", json!({ "primaryKey": "sku" }))
+        .await;
+    assert_eq!(code, 404, "{}", response);
+    assert_eq!(response["errorCode"], "index_not_found");
+}
@@ -0,0 +1,134 @@
+use serde_json::json;
+
+use crate::common::Server;
+
+#[actix_rt::test]
+async fn clone_index_settings() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(json!([{ "id": 1, "title": "hello" }]), None)
+        .await;
+    index
+        .update_settings(
+            json!({ "rankingRules": ["sort", "words"], "filterableAttributes": ["title"] }),
+        )
+        .await;
+    index.wait_update_id(1).await;
+
+    let (response, code) = index
+        .service
+        .post("/indexes/test/clone", json!({ "uid": "cloned" }))
+        .await;
+    assert_eq!(code, 201, "{}", response);
+    assert_eq!(response["uid"], "cloned");
+    assert_eq!(response["primaryKey"], "id");
+
+    let cloned = server.index("cloned");
+    let (settings, code) = cloned.settings().await;
+    assert_eq!(code, 200);
+    let (original_settings, _) = index.settings().await;
+    assert_eq!(settings, original_settings);
+
+    let (stats, code) = cloned.stats().await;
+    assert_eq!(code, 200);
+    assert_eq!(stats["numberOfDocuments"], 0);
+}
+
+#[actix_rt::test]
+async fn clone_index_documents() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(json!([{ "id": 1, "title": "hello" }]), None)
+        .await;
+    index.wait_update_id(0).await;
+
+    let (response, code) = index
+        .service
+        .post(
+            "/indexes/test/clone",
+            json!({ "uid": "cloned", "copyDocuments": true }),
+        )
+        .await;
+    assert_eq!(code, 201, "{}", response);
+
+    let cloned = server.index("cloned");
+    let (response, code) = cloned.search_post(json!({ "q": "hello" })).await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["hits"][0]["id"], 1);
+
+    // The clone is independent from the original index.
+    cloned
+        .add_documents(json!([{ "id": 2, "title": "world" }]), None)
+        .await;
+    cloned.wait_update_id(0).await;
+    let (stats, _) = index.stats().await;
+    assert_eq!(stats["numberOfDocuments"], 1);
+    let (stats, _) = cloned.stats().await;
+    assert_eq!(stats["numberOfDocuments"], 2);
+}
+
+#[actix_rt::test]
+async fn clone_index_drops_user_metadata() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    let (response, code) = index
+        .service
+        .post(
+            "/indexes",
+            json!({ "uid": "test", "metadata": { "owner": "search-team" } }),
+        )
+        .await;
+    assert_eq!(code, 201, "{}", response);
+
+    for copy_documents in [false, true] {
+        let uid = format!("cloned-{}", copy_documents);
+        let (response, code) = index
+            .service
+            .post(
+                "/indexes/test/clone",
+                json!({ "uid": uid, "copyDocuments": copy_documents }),
+            )
+            .await;
+        assert_eq!(code, 201, "{}", response);
+        assert_eq!(response["metadata"]["owner"], serde_json::Value::Null);
+
+        let (response, code) = server.index(&uid).get().await;
+        assert_eq!(code, 200);
+        assert_eq!(response["metadata"]["owner"], serde_json::Value::Null);
+    }
+}
+
+#[actix_rt::test]
+async fn clone_unexisting_index() {
+    let server = Server::new().await;
+
+    let (response, code) = server
+        .service
+        .post("/indexes/test/clone", json!({ "uid": "cloned" }))
+        .await;
+    assert_eq!(code, 404, "{}", response);
+    assert_eq!(response["errorCode"], "index_not_found");
+}
+
+#[actix_rt::test]
+async fn clone_index_to_existing_uid() {
+    let server = Server::new().await;
+    server.index("test").create(None).await;
+    server.index("other").create(None).await;
+
+    let (response, code) = server
+        .service
+        .post("/indexes/test/clone", json!({ "uid": "other" }))
+        .await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "index_already_exists");
+
+    let (response, code) = server
+        .service
+        .post("/indexes/test/clone", json!({ "uid": "in valid" }))
+        .await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "invalid_index_uid");
+}
@@ -14,7 +14,7 @@ async fn create_index_no_primary_key() {
     assert!(response.get("updatedAt").is_some());
     assert_eq!(response["createdAt"], response["updatedAt"]);
     assert_eq!(response["primaryKey"], Value::Null);
-    assert_eq!(response.as_object().unwrap().len(), 5);
+    assert_eq!(response.as_object().unwrap().len(), 6);
 }
 
 #[actix_rt::test]
@@ -30,7 +30,7 @@ async fn create_index_with_primary_key() {
     assert!(response.get("updatedAt").is_some());
     //assert_eq!(response["createdAt"], response["updatedAt"]);
     assert_eq!(response["primaryKey"], "primary");
-    assert_eq!(response.as_object().unwrap().len(), 5);
+    assert_eq!(response.as_object().unwrap().len(), 6);
 }
 
 #[actix_rt::test]
@@ -88,3 +88,42 @@ async fn test_create_multiple_indexes() {
     assert_eq!(index3.get().await.1, 200);
     assert_eq!(index4.get().await.1, 404);
 }
+
+#[actix_rt::test]
+async fn create_index_with_too_small_map_size() {
+    let server = Server::new().await;
+
+    let (response, code) = server
+        .service
+        .post("/indexes", json!({ "uid": "test", "mapSize": 4096 }))
+        .await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "bad_request");
+}
+
+#[actix_rt::test]
+async fn full_index_map_grows() {
+    let server = Server::new().await;
+    let (response, code) = server
+        .service
+        .post("/indexes", json!({ "uid": "test", "mapSize": 1024 * 1024 }))
+        .await;
+    assert_eq!(code, 201, "{}", response);
+
+    // Several megabytes of distinct words don't fit in a map of 1 MiB.
+    let documents: Vec<_> = (0..2000)
+        .map(|id| {
+            let text: Vec<_> = (0..100).map(|i| format!("word{}x{}", id, i)).collect();
+            json!({ "id": id, "text": text.join(" ") })
+        })
+        .collect();
+
+    let index = server.index("test");
+    index.add_documents(json!(documents), Some("id")).await;
+    let update = index.wait_update_id(0).await;
+    assert_eq!(update["status"], "processed", "{}", update);
+
+    let (response, code) = index.stats().await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["numberOfDocuments"], 2000);
+}
@@ -1,4 +1,6 @@
+use crate::common::server::default_settings;
 use crate::common::Server;
+use meilisearch_http::Opt;
 use serde_json::Value;
 
 #[actix_rt::test]
@@ -18,7 +20,7 @@ async fn create_and_get_index() {
     assert!(response.get("updatedAt").is_some());
     assert_eq!(response["createdAt"], response["updatedAt"]);
     assert_eq!(response["primaryKey"], Value::Null);
-    assert_eq!(response.as_object().unwrap().len(), 5);
+    assert_eq!(response.as_object().unwrap().len(), 6);
 }
 
 // TODO: partial test since we are testing error, and error is not yet fully implemented in
@@ -38,8 +40,9 @@ async fn no_index_return_empty_list() {
     let server = Server::new().await;
     let (response, code) = server.list_indexes().await;
     assert_eq!(code, 200);
-    assert!(response.is_array());
-    assert!(response.as_array().unwrap().is_empty());
+    assert!(response["results"].is_array());
+    assert!(response["results"].as_array().unwrap().is_empty());
+    assert_eq!(response["total"], 0);
 }
 
 #[actix_rt::test]
@@ -50,9 +53,10 @@ async fn list_multiple_indexes() {
 
     let (response, code) = server.list_indexes().await;
     assert_eq!(code, 200);
-    assert!(response.is_array());
-    let arr = response.as_array().unwrap();
+    assert!(response["results"].is_array());
+    let arr = response["results"].as_array().unwrap();
     assert_eq!(arr.len(), 2);
+    assert_eq!(response["total"], 2);
     assert!(arr
         .iter()
         .any(|entry| entry["uid"] == "test" && entry["primaryKey"] == Value::Null));
@@ -60,3 +64,77 @@ async fn list_multiple_indexes() {
         .iter()
         .any(|entry| entry["uid"] == "test1" && entry["primaryKey"] == "key"));
 }
+
+#[actix_rt::test]
+async fn list_indexes_without_limit() {
+    let server = Server::new().await;
+    for i in 0..25 {
+        server.index(format!("test-{}", i)).create(None).await;
+    }
+
+    let (response, code) = server.list_indexes().await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["results"].as_array().unwrap().len(), 25);
+    assert_eq!(response["limit"], Value::Null);
+    assert_eq!(response["total"], 25);
+}
+
+#[actix_rt::test]
+async fn list_indexes_paginated_and_filtered() {
+    let server = Server::new().await;
+    for uid in ["movies", "movies-2020", "movies-2021", "products", "songs"] {
+        server.index(uid).create(None).await;
+    }
+
+    let (response, code) = server.service.get("/indexes?offset=1&limit=2").await;
+    assert_eq!(code, 200, "{}", response);
+    let uids: Vec<_> = response["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|index| index["uid"].as_str().unwrap())
+        .collect();
+    assert_eq!(uids, ["movies-2020", "movies-2021"]);
+    assert_eq!(response["offset"], 1);
+    assert_eq!(response["limit"], 2);
+    assert_eq!(response["total"], 5);
+
+    let (response, code) = server
+        .service
+        .get("/indexes?uidPrefix=movies-&limit=1")
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["results"].as_array().unwrap().len(), 1);
+    assert_eq!(response["results"][0]["uid"], "movies-2020");
+    assert_eq!(response["total"], 2);
+
+    let (response, code) = server.service.get("/indexes?offset=10").await;
+    assert_eq!(code, 200, "{}", response);
+    assert!(response["results"].as_array().unwrap().is_empty());
+    assert_eq!(response["total"], 5);
+}
+
+#[actix_rt::test]
+async fn closed_indexes_are_reopened() {
+    let temp = tempfile::tempdir().unwrap();
+    let options = Opt {
+        max_open_indexes: Some(1),
+        ..default_settings(temp.path())
+    };
+    let server = Server::new_with_options(options).await;
+
+    for uid in ["first", "second", "third"] {
+        let index = server.index(uid);
+        index
+            .add_documents(serde_json::json!([{ "id": 1, "uid": uid }]), None)
+            .await;
+        index.wait_update_id(0).await;
+    }
+
+    // Each index is closed when the next one is opened, and reopened here.
+    for uid in ["first", "second", "third", "first"] {
+        let (response, code) = server.index(uid).get_document(1, None).await;
+        assert_eq!(code, 200, "{}", response);
+        assert_eq!(response["uid"], uid);
+    }
+}
@@ -0,0 +1,89 @@
+use serde_json::json;
+
+use crate::common::Server;
+
+#[actix_rt::test]
+async fn swap_indexes() {
+    let server = Server::new().await;
+    let products = server.index("products");
+    let products_new = server.index("products_new");
+    products
+        .add_documents(json!([{ "id": 1, "version": "old" }]), None)
+        .await;
+    products.wait_update_id(0).await;
+    products_new
+        .add_documents(json!([{ "id": 1, "version": "new" }]), None)
+        .await;
+
+    let (response, code) = server
+        .swap_indexes(json!([{ "indexes": ["products", "products_new"] }]))
+        .await;
+    assert_eq!(code, 200, "{}", response);
+
+    // The pending addition was processed before the swap.
+    let (response, code) = products.get_document(1, None).await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["version"], "new");
+    let (response, code) = products_new.get_document(1, None).await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["version"], "old");
+
+    // The updates follow their index.
+    let (response, code) = products.get_update(0).await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["status"], "processed");
+}
+
+#[actix_rt::test]
+async fn swap_unexisting_index() {
+    let server = Server::new().await;
+    server.index("products").create(None).await;
+
+    let (response, code) = server
+        .swap_indexes(json!([{ "indexes": ["products", "products_new"] }]))
+        .await;
+    assert_eq!(code, 404, "{}", response);
+    assert_eq!(response["errorCode"], "index_not_found");
+
+    let (_response, code) = server.index("products").get().await;
+    assert_eq!(code, 200);
+}
+
+#[actix_rt::test]
+async fn swap_index_twice() {
+    let server = Server::new().await;
+    for uid in ["a", "b", "c"] {
+        server.index(uid).create(None).await;
+    }
+
+    let (response, code) = server
+        .swap_indexes(json!([
+            { "indexes": ["a", "b"] },
+            { "indexes": ["b", "c"] },
+        ]))
+        .await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "bad_request");
+}
+
+#[actix_rt::test]
+async fn swap_several_indexes_at_once() {
+    let server = Server::new().await;
+    for uid in ["a", "b", "c", "d"] {
+        server.index(uid).create(Some(uid)).await;
+    }
+
+    let (response, code) = server
+        .swap_indexes(json!([
+            { "indexes": ["a", "b"] },
+            { "indexes": ["c", "d"] },
+        ]))
+        .await;
+    assert_eq!(code, 200, "{}", response);
+
+    for (uid, primary_key) in [("a", "b"), ("b", "a"), ("c", "d"), ("d", "c")] {
+        let (response, code) = server.index(uid).get().await;
+        assert_eq!(code, 200, "{}", response);
+        assert_eq!(response["primaryKey"], primary_key, "{}", response);
+    }
+}
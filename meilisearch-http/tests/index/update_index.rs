@@ -22,7 +22,7 @@ async fn update_primary_key() {
     assert!(created_at < updated_at);
 
     assert_eq!(response["primaryKey"], "primary");
-    assert_eq!(response.as_object().unwrap().len(), 5);
+    assert_eq!(response.as_object().unwrap().len(), 6);
 }
 
 #[actix_rt::test]
@@ -62,3 +62,211 @@ async fn test_unexisting_index() {
     let (_response, code) = server.index("test").update(None).await;
     assert_eq!(code, 404);
 }
+
+#[actix_rt::test]
+async fn rename_index() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+    index
+        .add_documents(serde_json::json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+    index.wait_update_id(0).await;
+
+    let (response, code) = index
+        .service
+        .put("/indexes/test", serde_json::json!({ "uid": "renamed" }))
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["uid"], "renamed");
+    assert_eq!(response["primaryKey"], "id");
+
+    let (_response, code) = index.get().await;
+    assert_eq!(code, 404);
+
+    let renamed = server.index("renamed");
+    let (response, code) = renamed.get_document(1, None).await;
+    assert_eq!(code, 200, "{}", response);
+    let (response, code) = renamed.get_update(0).await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["status"], "processed");
+}
+
+#[actix_rt::test]
+async fn rename_index_to_existing_uid() {
+    let server = Server::new().await;
+    server.index("test").create(None).await;
+    server.index("other").create(None).await;
+
+    let (response, code) = server
+        .service
+        .put(
+            "/indexes/test",
+            serde_json::json!({ "uid": "other", "primaryKey": "id" }),
+        )
+        .await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "index_already_exists");
+
+    // Nothing was updated.
+    let (response, code) = server.index("test").get().await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["primaryKey"], serde_json::json!(null));
+}
+
+#[actix_rt::test]
+async fn rename_index_to_invalid_uid() {
+    let server = Server::new().await;
+    server.index("test").create(None).await;
+
+    let (response, code) = server
+        .service
+        .put("/indexes/test", serde_json::json!({ "uid": "in valid" }))
+        .await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "invalid_index_uid");
+
+    let (_response, code) = server.index("test").get().await;
+    assert_eq!(code, 200);
+}
+
+#[actix_rt::test]
+async fn update_user_metadata() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    let (response, code) = index
+        .service
+        .post(
+            "/indexes",
+            serde_json::json!({
+                "uid": "test",
+                "metadata": { "owner": "search-team", "labels": { "env": "staging" } },
+            }),
+        )
+        .await;
+    assert_eq!(code, 201, "{}", response);
+    assert_eq!(response["metadata"]["owner"], "search-team");
+    assert_eq!(response["metadata"]["description"], serde_json::Value::Null);
+    assert_eq!(response["metadata"]["labels"]["env"], "staging");
+
+    let metadata = serde_json::json!({
+        "owner": "search-team",
+        "description": "Products of the catalog",
+        "labels": { "env": "production", "replicas": 2 },
+    });
+    let (response, code) = index
+        .service
+        .put(
+            "/indexes/test",
+            serde_json::json!({ "primaryKey": "id", "metadata": metadata }),
+        )
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["primaryKey"], "id");
+    assert_eq!(response["metadata"], metadata);
+
+    let (response, code) = index.get().await;
+    assert_eq!(code, 200);
+    assert_eq!(response["metadata"], metadata);
+
+    let (response, code) = server.list_indexes().await;
+    assert_eq!(code, 200);
+    assert_eq!(response["results"][0]["metadata"], metadata);
+
+    // the metadata is kept when an update doesn't set it
+    let (response, code) = index.update(None).await;
+    assert_eq!(code, 200);
+    assert_eq!(response["metadata"], metadata);
+}
+
+#[actix_rt::test]
+async fn update_invalid_user_metadata() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(None).await;
+
+    let (_response, code) = index
+        .service
+        .put(
+            "/indexes/test",
+            serde_json::json!({ "metadata": { "labels": ["not", "an", "object"] } }),
+        )
+        .await;
+    assert_eq!(code, 400);
+
+    let (response, code) = index.get().await;
+    assert_eq!(code, 200);
+    assert_eq!(
+        response["metadata"],
+        serde_json::json!({ "owner": null, "description": null, "labels": {} })
+    );
+}
+
+#[actix_rt::test]
+async fn update_display_name() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    let (response, code) = index
+        .service
+        .post(
+            "/indexes",
+            serde_json::json!({ "uid": "test", "name": "Test catalog" }),
+        )
+        .await;
+    assert_eq!(code, 201, "{}", response);
+    assert_eq!(response["uid"], "test");
+    assert_eq!(response["name"], "Test catalog");
+
+    let (response, code) = index
+        .service
+        .put("/indexes/test", serde_json::json!({ "name": "Products" }))
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["uid"], "test");
+    assert_eq!(response["name"], "Products");
+
+    // the display name is kept when the index is renamed
+    let (response, code) = index
+        .service
+        .put("/indexes/test", serde_json::json!({ "uid": "products" }))
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["uid"], "products");
+    assert_eq!(response["name"], "Products");
+
+    let (response, code) = server.index("products").get().await;
+    assert_eq!(code, 200);
+    assert_eq!(response["name"], "Products");
+
+    let (response, code) = server.list_indexes().await;
+    assert_eq!(code, 200);
+    assert_eq!(response["results"][0]["name"], "Products");
+
+    let (response, code) = index
+        .service
+        .put("/indexes/products", serde_json::json!({ "name": null }))
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    // Without a display name, the uid of the index is displayed.
+    assert_eq!(response["name"], "products");
+}
+
+#[actix_rt::test]
+async fn update_user_metadata_changes_updated_at() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    let (response, code) = index.create(None).await;
+    assert_eq!(code, 201, "{}", response);
+    let created_at = DateTime::parse_from_rfc3339(response["updatedAt"].as_str().unwrap()).unwrap();
+
+    let (response, code) = index
+        .service
+        .put(
+            "/indexes/test",
+            serde_json::json!({ "metadata": { "owner": "search-team" } }),
+        )
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    let updated_at = DateTime::parse_from_rfc3339(response["updatedAt"].as_str().unwrap()).unwrap();
+    assert!(updated_at > created_at);
+}
@@ -5,6 +5,7 @@ mod search;
 mod settings;
 mod snapshot;
 mod stats;
+mod tasks;
 mod updates;
 
 // Tests are isolated by features in different modules to allow better readability, test
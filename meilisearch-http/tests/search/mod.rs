@@ -267,3 +267,230 @@ async fn displayed_attributes() {
     assert_eq!(code, 200, "{}", response);
     assert!(response["hits"].get("title").is_none());
 }
+
+static NESTED_DOCUMENTS: Lazy<Value> = Lazy::new(|| {
+    json!([
+        {
+            "id": 852,
+            "title": "Les Misérables",
+            "author": { "name": "Victor Hugo", "birth": 1802 }
+        },
+        {
+            "id": 853,
+            "title": "Madame Bovary",
+            "author": { "name": "Gustave Flaubert", "birth": 1821 }
+        }
+    ])
+});
+
+#[actix_rt::test]
+async fn search_nested_fields() {
+    let server = Server::new().await;
+    let index = server.index("test");
+
+    index
+        .update_settings(json!({
+            "filterableAttributes": ["author.name"],
+            "sortableAttributes": ["author.birth"],
+            "searchableAttributes": ["title", "author.name"]
+        }))
+        .await;
+
+    let documents = NESTED_DOCUMENTS.clone();
+    index.add_documents(documents, None).await;
+    index.wait_update_id(1).await;
+
+    index
+        .search(json!({"q": "flaubert"}), |response, code| {
+            assert_eq!(code, 200, "{}", response);
+            assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+            assert_eq!(
+                response["hits"][0]["author"],
+                json!({ "name": "Gustave Flaubert", "birth": 1821 })
+            );
+        })
+        .await;
+
+    index
+        .search(
+            json!({"filter": "author.name = \"Victor Hugo\""}),
+            |response, code| {
+                assert_eq!(code, 200, "{}", response);
+                assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+                assert_eq!(response["hits"][0]["id"], 852);
+            },
+        )
+        .await;
+
+    index
+        .search(json!({"sort": ["author.birth:desc"]}), |response, code| {
+            assert_eq!(code, 200, "{}", response);
+            assert_eq!(response["hits"][0]["id"], 853);
+        })
+        .await;
+
+    index
+        .search(
+            json!({"attributesToRetrieve": ["author.name"]}),
+            |response, code| {
+                assert_eq!(code, 200, "{}", response);
+                assert_eq!(
+                    response["hits"][0],
+                    json!({ "author": { "name": "Victor Hugo" } })
+                );
+            },
+        )
+        .await;
+}
+
+#[actix_rt::test]
+async fn filter_on_object_attribute_declared_before_documents() {
+    let server = Server::new().await;
+    let index = server.index("test");
+
+    index
+        .update_settings(json!({
+            "filterableAttributes": ["author"],
+            "sortableAttributes": ["author"]
+        }))
+        .await;
+
+    let documents = NESTED_DOCUMENTS.clone();
+    index.add_documents(documents, None).await;
+    index.wait_update_id(1).await;
+
+    index
+        .search(
+            json!({"filter": "author.name = \"Victor Hugo\""}),
+            |response, code| {
+                assert_eq!(code, 200, "{}", response);
+                assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+                assert_eq!(response["hits"][0]["id"], 852);
+            },
+        )
+        .await;
+
+    index
+        .search(json!({"sort": ["author.birth:desc"]}), |response, code| {
+            assert_eq!(code, 200, "{}", response);
+            assert_eq!(response["hits"][0]["id"], 853);
+        })
+        .await;
+
+    // The settings are returned as declared.
+    let (response, code) = index.settings().await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["filterableAttributes"], json!(["author"]));
+    assert_eq!(response["sortableAttributes"], json!(["author"]));
+}
+
+#[actix_rt::test]
+async fn filter_on_object_attribute_declared_after_documents() {
+    let server = Server::new().await;
+    let index = server.index("test");
+
+    let documents = NESTED_DOCUMENTS.clone();
+    index.add_documents(documents, None).await;
+    index.wait_update_id(0).await;
+
+    index
+        .update_settings(json!({
+            "filterableAttributes": ["author"],
+            "searchableAttributes": ["author"]
+        }))
+        .await;
+    index.wait_update_id(1).await;
+
+    index
+        .search(
+            json!({"filter": "author.birth = 1821"}),
+            |response, code| {
+                assert_eq!(code, 200, "{}", response);
+                assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+                assert_eq!(response["hits"][0]["id"], 853);
+            },
+        )
+        .await;
+
+    index
+        .search(json!({"q": "hugo"}), |response, code| {
+            assert_eq!(code, 200, "{}", response);
+            assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+            assert_eq!(response["hits"][0]["id"], 852);
+        })
+        .await;
+
+    // The title isn't searchable anymore.
+    index
+        .search(json!({"q": "bovary"}), |response, code| {
+            assert_eq!(code, 200, "{}", response);
+            assert!(response["hits"].as_array().unwrap().is_empty());
+        })
+        .await;
+}
+
+#[actix_rt::test]
+async fn dotted_field_names_are_not_nested() {
+    let server = Server::new().await;
+    let index = server.index("test");
+
+    let documents = json!([
+        { "id": 1, "author.name": "Victor Hugo", "editor": { "name": "Hetzel" } }
+    ]);
+    index.add_documents(documents, None).await;
+    index.wait_update_id(0).await;
+
+    index
+        .search(json!({"q": "hugo"}), |response, code| {
+            assert_eq!(code, 200, "{}", response);
+            assert_eq!(
+                response["hits"][0],
+                json!({ "id": 1, "author.name": "Victor Hugo", "editor": { "name": "Hetzel" } })
+            );
+        })
+        .await;
+}
+
+#[actix_rt::test]
+async fn nested_fields_added_to_a_non_empty_index_need_a_settings_update() {
+    let server = Server::new().await;
+    let index = server.index("test");
+
+    index
+        .update_settings(json!({ "filterableAttributes": ["author"] }))
+        .await;
+    let documents = NESTED_DOCUMENTS.clone();
+    index.add_documents(documents, None).await;
+    index.wait_update_id(1).await;
+    let documents = json!([
+        { "id": 854, "title": "Germinal", "author": { "name": "Émile Zola", "country": "France" } }
+    ]);
+    index.add_documents(documents, None).await;
+    index.wait_update_id(2).await;
+
+    // The new nested field isn't expanded, since it would reindex all the documents.
+    index
+        .search(
+            json!({"filter": "author.country = France"}),
+            |response, code| {
+                assert_eq!(code, 400, "{}", response);
+            },
+        )
+        .await;
+
+    index
+        .update_settings(json!({ "filterableAttributes": ["author"] }))
+        .await;
+    index.wait_update_id(3).await;
+
+    index
+        .search(
+            json!({"filter": "author.country = France"}),
+            |response, code| {
+                assert_eq!(code, 200, "{}", response);
+                assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+                assert_eq!(response["hits"][0]["id"], 854);
+            },
+        )
+        .await;
+}
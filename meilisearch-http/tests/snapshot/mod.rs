@@ -22,6 +22,7 @@ async fn perform_snapshot() {
     let server = Server::new_with_options(options).await;
     let index = server.index("test");
     index.load_test_set().await;
+    server.put_alias("alias", "test").await;
 
     let (response, _) = index
         .get_all_documents(GetAllDocumentsOptions::default())
@@ -49,4 +50,8 @@ async fn perform_snapshot() {
         .await;
 
     assert_eq!(response, response_from_snapshot);
+
+    let (response, code) = server.get_alias("alias").await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["indexUid"], "test");
 }
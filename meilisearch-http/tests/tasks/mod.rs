@@ -0,0 +1,393 @@
+use std::time::Duration;
+
+use actix_web::web::Bytes;
+use chrono::DateTime;
+use futures::{Stream, StreamExt};
+use meilisearch_http::Opt;
+use serde_json::{json, Value};
+use tokio::time::{sleep, timeout};
+
+use crate::common::server::default_settings;
+use crate::common::Server;
+
+#[actix_rt::test]
+async fn list_tasks_empty() {
+    let server = Server::new().await;
+    let (response, code) = server.tasks("").await;
+    assert_eq!(code, 200);
+    assert!(response["results"].as_array().unwrap().is_empty());
+    assert_eq!(response["next"], json!(null));
+}
+
+#[actix_rt::test]
+async fn list_tasks_across_indexes() {
+    let server = Server::new().await;
+    let first = server.index("first");
+    let second = server.index("second");
+
+    first
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+    second
+        .add_documents(json!([{ "id": 1, "content": "bar" }]), None)
+        .await;
+    first
+        .update_settings(json!({ "rankingRules": ["wrong"] }))
+        .await;
+    first.wait_update_id(1).await;
+    second.wait_update_id(0).await;
+
+    let (response, code) = server.tasks("").await;
+    assert_eq!(code, 200, "{}", response);
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    // the most recent tasks come first
+    assert_eq!(results[0]["uid"], 2);
+    assert_eq!(results[0]["indexUid"], "first");
+    assert_eq!(results[0]["updateId"], 1);
+    assert_eq!(results[1]["uid"], 1);
+    assert_eq!(results[1]["indexUid"], "second");
+    assert_eq!(results[2]["uid"], 0);
+
+    let (response, _) = server.tasks("indexUid=second").await;
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["indexUid"], "second");
+
+    let (response, _) = server.tasks("status=failed").await;
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["uid"], 1);
+
+    let (response, _) = server.tasks("type=DocumentsAddition").await;
+    assert_eq!(response["results"].as_array().unwrap().len(), 2);
+
+    let (response, _) = server.tasks("enqueuedBefore=2000-01-01T00:00:00Z").await;
+    assert!(response["results"].as_array().unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn list_tasks_pagination() {
+    let server = Server::new().await;
+    let index = server.index("test");
+
+    for id in 0..3 {
+        index
+            .add_documents(json!([{ "id": id, "content": "foo" }]), None)
+            .await;
+    }
+    index.wait_update_id(2).await;
+
+    let (response, code) = server.tasks("limit=2").await;
+    assert_eq!(code, 200, "{}", response);
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["uid"], 2);
+    assert_eq!(response["next"], 0);
+
+    let (response, _) = server.tasks("limit=2&from=0").await;
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["uid"], 0);
+    assert_eq!(response["next"], json!(null));
+}
+
+#[actix_rt::test]
+async fn list_tasks_pagination_with_filter() {
+    let server = Server::new().await;
+    let first = server.index("first");
+    let second = server.index("second");
+
+    for id in 0..3 {
+        first
+            .add_documents(json!([{ "id": id, "content": "foo" }]), None)
+            .await;
+    }
+    for id in 0..3 {
+        second
+            .add_documents(json!([{ "id": id, "content": "foo" }]), None)
+            .await;
+    }
+    first.wait_update_id(2).await;
+    second.wait_update_id(2).await;
+
+    // The matching tasks are older than a whole page of tasks of the other index.
+    let (response, code) = server.tasks("limit=2&indexUid=first").await;
+    assert_eq!(code, 200, "{}", response);
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["uid"], 2);
+    assert_eq!(results[1]["uid"], 1);
+    assert_eq!(response["next"], 0);
+
+    let (response, _) = server.tasks("limit=2&indexUid=first&from=0").await;
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["uid"], 0);
+    assert_eq!(response["next"], json!(null));
+}
+
+#[actix_rt::test]
+async fn list_tasks_bad_parameter() {
+    let server = Server::new().await;
+    let (_response, code) = server.tasks("unknown=true").await;
+    assert_eq!(code, 400);
+}
+
+#[actix_rt::test]
+async fn cancel_tasks_ignores_processed_tasks() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+    index.wait_update_id(0).await;
+
+    let (response, code) = server.service.post("/tasks/cancel", json!(null)).await;
+    assert_eq!(code, 200, "{}", response);
+    assert!(response["results"].as_array().unwrap().is_empty());
+
+    let (response, _) = index.get_update(0).await;
+    assert_eq!(response["status"], "processed");
+}
+
+#[actix_rt::test]
+async fn cancel_enqueued_tasks() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    for id in 0..10 {
+        index
+            .add_documents(json!([{ "id": id, "content": "foo" }]), None)
+            .await;
+    }
+
+    let (response, code) = server
+        .service
+        .post(
+            "/tasks/cancel?indexUid=test&type=DocumentsAddition",
+            json!(null),
+        )
+        .await;
+    assert_eq!(code, 200, "{}", response);
+
+    // The tasks that were still enqueued are aborted, the others are processed normally.
+    for task in response["results"].as_array().unwrap() {
+        assert_eq!(task["status"], "aborted");
+        let (update, _) = index.get_update(task["updateId"].as_u64().unwrap()).await;
+        assert_eq!(update["status"], "aborted");
+    }
+
+    let (response, _) = server.tasks("status=enqueued").await;
+    assert!(response["results"].as_array().unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn prune_tasks_max_count() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    for id in 0..4 {
+        index
+            .add_documents(json!([{ "id": id, "content": "foo" }]), None)
+            .await;
+    }
+    index.wait_update_id(3).await;
+
+    let (response, code) = server
+        .service
+        .post("/tasks/prune?maxCount=1", json!(null))
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["deletedTasks"], 3);
+
+    // The most recent task is kept.
+    let (response, _) = server.tasks("").await;
+    let results = response["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["uid"], 3);
+
+    let (_response, code) = index.get_update(0).await;
+    assert_eq!(code, 404);
+}
+
+#[actix_rt::test]
+async fn prune_tasks_of_selected_indexes() {
+    let server = Server::new().await;
+    let index1 = server.index("test1");
+    let index2 = server.index("test2");
+    index1
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+    index2
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+    index1.wait_update_id(0).await;
+    index2.wait_update_id(0).await;
+
+    let (response, code) = server
+        .service
+        .post("/tasks/prune?indexUid=test1&maxCount=0", json!(null))
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["deletedTasks"], 1);
+
+    let (_response, code) = index1.get_update(0).await;
+    assert_eq!(code, 404);
+    let (_response, code) = index2.get_update(0).await;
+    assert_eq!(code, 200);
+}
+
+#[actix_rt::test]
+async fn prune_tasks_unknown_index() {
+    let server = Server::new().await;
+    let (response, code) = server
+        .service
+        .post("/tasks/prune?indexUid=unknown&maxCount=0", json!(null))
+        .await;
+    assert_eq!(code, 404, "{}", response);
+}
+
+#[actix_rt::test]
+async fn prune_tasks_without_policy() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+    index.wait_update_id(0).await;
+
+    let (response, code) = server.service.post("/tasks/prune", json!(null)).await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "bad_request");
+
+    let (_response, code) = index.get_update(0).await;
+    assert_eq!(code, 200);
+}
+
+#[actix_rt::test]
+async fn retention_policy_prunes_history() {
+    let temp = tempfile::tempdir().unwrap();
+    let options = Opt {
+        updates_retention_max_count: Some(2),
+        ..default_settings(temp.path())
+    };
+    let server = Server::new_with_options(options).await;
+    let index = server.index("test");
+    for id in 0..4 {
+        index
+            .add_documents(json!([{ "id": id, "content": "foo" }]), None)
+            .await;
+    }
+    index.wait_update_id(3).await;
+
+    // The history is pruned once the update queue is empty.
+    for _ in 0..10 {
+        let (response, _) = index.list_updates().await;
+        if response.as_array().unwrap().len() == 2 {
+            return;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    panic!("the update history was not pruned");
+}
+
+#[actix_rt::test]
+async fn parallel_update_workers_keep_per_index_order() {
+    let temp = tempfile::tempdir().unwrap();
+    let options = Opt {
+        update_workers: 4,
+        ..default_settings(temp.path())
+    };
+    let server = Server::new_with_options(options).await;
+
+    let uids = ["test1", "test2", "test3"];
+    for version in 0..5 {
+        for uid in uids {
+            let documents = json!([{ "id": 1, "version": version }]);
+            let (response, code) = server.index(uid).add_documents(documents, None).await;
+            assert_eq!(code, 202, "{}", response);
+        }
+    }
+
+    for uid in uids {
+        let index = server.index(uid);
+        let mut last_processed_at = None;
+        for update_id in 0..5 {
+            let response = index.wait_update_id(update_id).await;
+            assert_eq!(response["status"], "processed", "{}", response);
+            let processed_at =
+                DateTime::parse_from_rfc3339(response["processedAt"].as_str().unwrap()).unwrap();
+            assert!(Some(processed_at) >= last_processed_at);
+            last_processed_at = Some(processed_at);
+        }
+
+        // The last addition was processed last.
+        let (response, code) = index.get_document(1, None).await;
+        assert_eq!(code, 200, "{}", response);
+        assert_eq!(response["version"], 4);
+    }
+}
+
+/// Reads `count` server-sent events from `stream`, as their name and data.
+async fn read_events(
+    stream: &mut (impl Stream<Item = Bytes> + Unpin),
+    count: usize,
+) -> Vec<(String, Value)> {
+    let mut buffer = String::new();
+    let mut events = Vec::new();
+    while events.len() < count {
+        let chunk = timeout(Duration::from_secs(10), stream.next())
+            .await
+            .expect("timeout waiting for an event")
+            .expect("the stream ended");
+        buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        while let Some(end) = buffer.find("\n\n") {
+            let event: String = buffer.drain(..end + 2).collect();
+            let mut lines = event.lines();
+            let name = lines.next().unwrap().strip_prefix("event: ").unwrap();
+            let data = lines.next().unwrap().strip_prefix("data: ").unwrap();
+            events.push((name.to_string(), serde_json::from_str(data).unwrap()));
+        }
+    }
+    events
+}
+
+#[actix_rt::test]
+async fn stream_tasks_status_changes() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(None).await;
+    server.index("other").create(None).await;
+
+    let (mut stream, code) = server
+        .service
+        .get_stream("/tasks/stream?indexUid=test")
+        .await;
+    assert_eq!(code, 200);
+
+    server
+        .index("other")
+        .add_documents(json!([{ "id": 1, "content": "bar" }]), None)
+        .await;
+    index
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+
+    let events = read_events(&mut stream, 3).await;
+    let names: Vec<_> = events.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["enqueued", "processing", "processed"]);
+    for (name, task) in events {
+        assert_eq!(task["indexUid"], "test", "{}", task);
+        assert_eq!(task["updateId"], 0, "{}", task);
+        assert_eq!(task["status"], name, "{}", task);
+    }
+}
+
+#[actix_rt::test]
+async fn stream_tasks_unknown_index() {
+    let server = Server::new().await;
+    let (_stream, code) = server
+        .service
+        .get_stream("/tasks/stream?indexUid=test")
+        .await;
+    assert_eq!(code, 404);
+}
@@ -1,3 +1,9 @@
+#[cfg(feature = "analytics")]
+mod webhook;
+
+use chrono::DateTime;
+use serde_json::{json, Value};
+
 use crate::common::Server;
 
 #[actix_rt::test]
@@ -67,3 +73,150 @@ async fn list_updates() {
     assert_eq!(code, 200);
     assert_eq!(response.as_array().unwrap().len(), 1);
 }
+
+fn processed_at(update: &Value) -> DateTime<chrono::FixedOffset> {
+    DateTime::parse_from_rfc3339(update["processedAt"].as_str().unwrap()).unwrap()
+}
+
+#[actix_rt::test]
+async fn prioritized_settings_overtake_document_additions() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+    // A large addition keeps the index busy while the next updates are registered.
+    index
+        .add_documents(
+            serde_json::from_str(include_str!("../assets/test_set.json")).unwrap(),
+            None,
+        )
+        .await;
+    let (response, code) = index
+        .service
+        .put(
+            "/indexes/test/documents",
+            json!([{ "id": 1, "content": "foo" }]),
+        )
+        .await;
+    assert_eq!(code, 202, "{}", response);
+    let (response, code) = index
+        .service
+        .post(
+            "/indexes/test/settings?priority=10",
+            json!({ "filterableAttributes": ["id"] }),
+        )
+        .await;
+    assert_eq!(code, 202, "{}", response);
+
+    index.wait_update_id(0).await;
+    let addition = index.wait_update_id(1).await;
+    let settings = index.wait_update_id(2).await;
+    assert_eq!(addition["status"], "processed", "{}", addition);
+    assert_eq!(settings["status"], "processed", "{}", settings);
+    assert!(processed_at(&settings) < processed_at(&addition));
+}
+
+#[actix_rt::test]
+async fn prioritized_update_keeps_order_of_conflicting_updates() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+    index
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+    let (response, code) = index
+        .service
+        .delete("/indexes/test/documents/1?priority=10")
+        .await;
+    assert_eq!(code, 202, "{}", response);
+
+    index.wait_update_id(0).await;
+    let deletion = index.wait_update_id(1).await;
+    assert_eq!(deletion["status"], "processed", "{}", deletion);
+
+    // The deletion was processed after the addition of the document.
+    let (_response, code) = index.get_document(1, None).await;
+    assert_eq!(code, 404);
+}
+
+#[actix_rt::test]
+async fn wait_update_returns_finished_update() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+    index
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+
+    let (response, code) = index
+        .service
+        .get("/indexes/test/updates/0/wait?timeout=1m")
+        .await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["status"], "processed", "{}", response);
+    assert_eq!(response["updateId"], 0);
+
+    // An already finished update is returned right away.
+    let (response, code) = index.service.get("/indexes/test/updates/0/wait").await;
+    assert_eq!(code, 200, "{}", response);
+    assert_eq!(response["status"], "processed", "{}", response);
+}
+
+#[actix_rt::test]
+async fn wait_update_invalid_timeout() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(None).await;
+    index
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+
+    for timeout in ["", "ten", "10h", "-1s", "1000m"].iter() {
+        let url = format!("/indexes/test/updates/0/wait?timeout={}", timeout);
+        let (response, code) = index.service.get(url).await;
+        assert_eq!(code, 400, "{}: {}", timeout, response);
+        assert_eq!(response["errorCode"], "bad_request", "{}", response);
+    }
+}
+
+#[actix_rt::test]
+async fn wait_unexisting_update() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(None).await;
+    let (_response, code) = index
+        .service
+        .get("/indexes/test/updates/0/wait?timeout=100ms")
+        .await;
+    assert_eq!(code, 404);
+}
+
+#[actix_rt::test]
+async fn indexing_progress_is_only_reported_while_processing() {
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(Some("id")).await;
+    let documents: Vec<_> = (0..1000)
+        .map(|id| json!({ "id": id, "content": format!("document {}", id) }))
+        .collect();
+    index.add_documents(json!(documents), None).await;
+
+    loop {
+        let (response, code) = index.get_update(0).await;
+        assert_eq!(code, 200, "{}", response);
+        match response["status"].as_str().unwrap() {
+            "enqueued" => assert!(response.get("progress").is_none(), "{}", response),
+            "processing" => {
+                if let Some(progress) = response.get("progress") {
+                    assert_eq!(progress["totalSteps"], 4, "{}", response);
+                    assert!(progress["step"].is_string(), "{}", response);
+                }
+            }
+            _ => {
+                assert_eq!(response["status"], "processed", "{}", response);
+                assert!(response.get("progress").is_none(), "{}", response);
+                break;
+            }
+        }
+        actix_rt::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+}
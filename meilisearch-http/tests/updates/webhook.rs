@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use meilisearch_http::Opt;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use crate::common::server::default_settings;
+use crate::common::Server;
+
+/// Starts a local http server answering the requests with the given `statuses`, and then with
+/// 200. Returns its url, and a receiver of the bodies of the requests.
+async fn webhook_stub(statuses: Vec<u16>) -> (String, mpsc::UnboundedReceiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    tokio::task::spawn(async move {
+        let mut statuses = statuses.into_iter();
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let body = read_body(&mut stream).await;
+            let status = statuses.next().unwrap_or(200);
+            let response = format!(
+                "HTTP/1.1 {} Stub\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                status
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            let _ = sender.send(serde_json::from_slice(&body).unwrap());
+        }
+    });
+
+    (url, receiver)
+}
+
+/// Reads an http request from `stream`, and returns its body.
+async fn read_body(stream: &mut TcpStream) -> Vec<u8> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    loop {
+        let read = stream.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request);
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length: usize = text[..end]
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                .map_or(0, |(_, value)| value.trim().parse().unwrap());
+            if request.len() >= end + 4 + length {
+                return request[end + 4..end + 4 + length].to_vec();
+            }
+        }
+    }
+}
+
+async fn next_call(calls: &mut mpsc::UnboundedReceiver<Value>) -> Value {
+    timeout(Duration::from_secs(10), calls.recv())
+        .await
+        .expect("timeout waiting for the webhook call")
+        .unwrap()
+}
+
+#[actix_rt::test]
+async fn default_webhook_is_notified_of_finished_updates() {
+    let (url, mut calls) = webhook_stub(Vec::new()).await;
+    let temp = tempfile::tempdir().unwrap();
+    let options = Opt {
+        update_webhook_url: Some(url),
+        ..default_settings(temp.path())
+    };
+    let server = Server::new_with_options(options).await;
+    let index = server.index("test");
+    index
+        .add_documents(json!([{ "id": 1, "content": "foo" }]), None)
+        .await;
+
+    let call = next_call(&mut calls).await;
+    assert_eq!(call["status"], "processed", "{}", call);
+    assert_eq!(call["indexUid"], "test", "{}", call);
+    assert_eq!(call["updateId"], 0, "{}", call);
+    assert_eq!(call["type"]["name"], "DocumentsAddition", "{}", call);
+}
+
+#[actix_rt::test]
+async fn update_webhook_overrides_default_webhook() {
+    let (default_url, mut default_calls) = webhook_stub(Vec::new()).await;
+    let (url, mut calls) = webhook_stub(Vec::new()).await;
+    let temp = tempfile::tempdir().unwrap();
+    let options = Opt {
+        update_webhook_url: Some(default_url),
+        ..default_settings(temp.path())
+    };
+    let server = Server::new_with_options(options).await;
+    server.index("test").create(None).await;
+
+    let (response, code) = server
+        .service
+        .delete(format!("/indexes/test/documents?webhook={}", url))
+        .await;
+    assert_eq!(code, 202, "{}", response);
+    let (response, code) = server.service.delete("/indexes/test/documents").await;
+    assert_eq!(code, 202, "{}", response);
+
+    let call = next_call(&mut calls).await;
+    assert_eq!(call["updateId"], 0, "{}", call);
+    let call = next_call(&mut default_calls).await;
+    assert_eq!(call["updateId"], 1, "{}", call);
+}
+
+#[actix_rt::test]
+async fn failed_webhook_calls_are_retried() {
+    let (url, mut calls) = webhook_stub(vec![500, 503]).await;
+    let server = Server::new().await;
+    server.index("test").create(None).await;
+
+    let (response, code) = server
+        .service
+        .delete(format!("/indexes/test/documents?webhook={}", url))
+        .await;
+    assert_eq!(code, 202, "{}", response);
+
+    for _ in 0..3 {
+        let call = next_call(&mut calls).await;
+        assert_eq!(call["status"], "processed", "{}", call);
+        assert_eq!(call["updateId"], 0, "{}", call);
+    }
+}
+
+#[actix_rt::test]
+async fn invalid_webhook_url() {
+    let server = Server::new().await;
+    server.index("test").create(None).await;
+
+    let (response, code) = server
+        .service
+        .delete("/indexes/test/documents?webhook=not-an-url")
+        .await;
+    assert_eq!(code, 400, "{}", response);
+    assert_eq!(response["errorCode"], "bad_request", "{}", response);
+}
+
+#[actix_rt::test]
+async fn webhook_url_is_not_returned() {
+    let (url, mut calls) = webhook_stub(Vec::new()).await;
+    let server = Server::new().await;
+    let index = server.index("test");
+    index.create(None).await;
+
+    let (response, code) = server
+        .service
+        .delete(format!("/indexes/test/documents?webhook={}", url))
+        .await;
+    assert_eq!(code, 202, "{}", response);
+
+    let call = next_call(&mut calls).await;
+    assert!(!call.to_string().contains(&url), "{}", call);
+
+    let (response, code) = index.get_update(0).await;
+    assert_eq!(code, 200);
+    assert!(!response.to_string().contains(&url), "{}", response);
+    let (response, code) = index.list_updates().await;
+    assert_eq!(code, 200);
+    assert!(!response.to_string().contains(&url), "{}", response);
+}
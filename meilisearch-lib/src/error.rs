@@ -35,8 +35,8 @@ impl ErrorCode for MilliError<'_> {
             milli::Error::UserError(ref error) => {
                 match error {
                     // TODO: wait for spec for new error codes.
+                    UserError::MaxDatabaseSizeReached => Code::DatabaseSizeLimitReached,
                     UserError::SerdeJson(_)
-                    | UserError::MaxDatabaseSizeReached
                     | UserError::InvalidDocumentId { .. }
                     | UserError::InvalidStoreFile
                     | UserError::NoSpaceLeftOnDevice
@@ -3,6 +3,7 @@ use std::io::{BufReader, Seek, SeekFrom, Write};
 use std::path::Path;
 
 use anyhow::Context;
+use heed::types::{SerdeJson, Str};
 use heed::{EnvOpenOptions, RoTxn};
 use indexmap::IndexMap;
 use milli::documents::DocumentBatchReader;
@@ -13,12 +14,19 @@ use crate::index::update_handler::UpdateHandler;
 use crate::index::updates::apply_settings_to_builder;
 
 use super::error::Result;
-use super::{Index, Settings, Unchecked};
+use super::nested::{expand_settings, expand_settings_before_indexing, flatten_batch};
+use super::{Index, IndexUserMetadata, Settings, Unchecked, NAME_KEY, USER_METADATA_KEY};
 
 #[derive(Serialize, Deserialize)]
 struct DumpMeta {
     settings: Settings<Unchecked>,
     primary_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    map_size: Option<usize>,
+    #[serde(default)]
+    metadata: IndexUserMetadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
 }
 
 const META_FILE_NAME: &str = "meta.json";
@@ -76,6 +84,9 @@ impl Index {
         let meta = DumpMeta {
             settings,
             primary_key,
+            map_size: self.map_size,
+            metadata: self.user_metadata(txn)?,
+            name: self.display_name(txn)?,
         };
 
         serde_json::to_writer(&mut meta_file, &meta)?;
@@ -102,16 +113,24 @@ impl Index {
         let DumpMeta {
             settings,
             primary_key,
+            map_size,
+            metadata,
+            name,
         } = serde_json::from_reader(meta_file)?;
         let settings = settings.check();
 
+        if let Some(map_size) = map_size {
+            Self::persist_map_size(&dst_dir_path, map_size)?;
+        }
+
         let mut options = EnvOpenOptions::new();
-        options.map_size(size);
+        options.map_size(map_size.unwrap_or(size));
         let index = milli::Index::new(options, &dst_dir_path)?;
 
         let mut txn = index.write_txn()?;
 
         // Apply settings first
+        let settings = expand_settings(&index, &mut txn, &settings)?;
         let builder = update_handler.update_builder(0);
         let mut builder = builder.settings(&mut txn, &index);
 
@@ -123,6 +142,15 @@ impl Index {
 
         builder.execute(|_, _| ())?;
 
+        index.main.put::<_, Str, SerdeJson<IndexUserMetadata>>(
+            &mut txn,
+            USER_METADATA_KEY,
+            &metadata,
+        )?;
+        if let Some(name) = name {
+            index.main.put::<_, Str, Str>(&mut txn, NAME_KEY, &name)?;
+        }
+
         let document_file_path = src.as_ref().join(DATA_FILE_NAME);
         let reader = BufReader::new(File::open(&document_file_path)?);
 
@@ -131,6 +159,8 @@ impl Index {
         read_ndjson(reader, &mut tmp_doc_file)?;
 
         tmp_doc_file.seek(SeekFrom::Start(0))?;
+        let tmp_doc_file = flatten_batch(&index, &mut txn, tmp_doc_file)?;
+        expand_settings_before_indexing(&index, &mut txn)?;
 
         let documents_reader = DocumentBatchReader::from_reader(tmp_doc_file)?;
 
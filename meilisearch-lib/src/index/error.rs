@@ -1,6 +1,8 @@
 use std::error::Error;
 
+use heed::MdbError;
 use meilisearch_error::{Code, ErrorCode};
+use milli::{InternalError, UserError};
 use serde_json::Value;
 
 use crate::error::MilliError;
@@ -19,23 +21,58 @@ pub enum IndexError {
     Milli(#[from] milli::Error),
     #[error("A primary key is already present. It's impossible to update it")]
     ExistingPrimaryKey,
+    #[error("Invalid document edition function: {0}")]
+    InvalidEditionFunction(String),
+    #[error("The document edition function exceeded the maximum of {0} operations for the whole update.")]
+    EditionOperationsExceeded(u64),
+    #[error("The document edition function changed the primary key of the document `{0}`.")]
+    EditedPrimaryKey(String),
+    #[error("The version of the document `{0}` must be a positive integer.")]
+    InvalidDocumentVersion(String),
+    #[error("The primary key can't be changed to `{0}`: {1}")]
+    InvalidPrimaryKeyChange(String, String),
 }
 
 internal_error!(
     IndexError: std::io::Error,
     heed::Error,
     fst::Error,
-    serde_json::Error
+    serde_json::Error,
+    milli::documents::Error,
+    crate::index_controller::update_file_store::UpdateFileStoreError
 );
 
+impl IndexError {
+    /// Returns `true` if the error is caused by the LMDB map of the index being full.
+    pub fn is_map_full(&self) -> bool {
+        match self {
+            IndexError::Milli(milli::Error::UserError(UserError::MaxDatabaseSizeReached))
+            | IndexError::Milli(milli::Error::InternalError(InternalError::Store(
+                MdbError::MapFull,
+            ))) => true,
+            IndexError::Internal(e) => matches!(
+                e.downcast_ref::<heed::Error>(),
+                Some(heed::Error::Mdb(MdbError::MapFull))
+            ),
+            _ => false,
+        }
+    }
+}
+
 impl ErrorCode for IndexError {
     fn error_code(&self) -> Code {
         match self {
+            _ if self.is_map_full() => Code::DatabaseSizeLimitReached,
             IndexError::Internal(_) => Code::Internal,
             IndexError::DocumentNotFound(_) => Code::DocumentNotFound,
             IndexError::Facet(e) => e.error_code(),
             IndexError::Milli(e) => MilliError(e).error_code(),
             IndexError::ExistingPrimaryKey => Code::PrimaryKeyAlreadyPresent,
+            IndexError::InvalidEditionFunction(_) => Code::InvalidDocumentEdition,
+            IndexError::EditionOperationsExceeded(_) => Code::InvalidDocumentEdition,
+            IndexError::EditedPrimaryKey(_) => Code::InvalidDocumentEdition,
+            IndexError::InvalidDocumentVersion(_) => Code::InvalidDocumentVersion,
+            IndexError::InvalidPrimaryKeyChange(..) => Code::InvalidPrimaryKeyChange,
         }
     }
 }
@@ -6,7 +6,8 @@ use std::path::Path;
 use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
-use heed::{EnvOpenOptions, RoTxn};
+use heed::types::{SerdeJson, Str};
+use heed::{EnvOpenOptions, RoTxn, RwTxn};
 use milli::update::Setting;
 use milli::{obkv_to_json, FieldDistribution, FieldId};
 use serde::{Deserialize, Serialize};
@@ -21,23 +22,66 @@ use crate::index_controller::update_file_store::UpdateFileStore;
 use crate::EnvSizer;
 
 use self::error::IndexError;
+use self::nested::{unflatten_document, DeclaredAttributes, NestedFields};
 use self::update_handler::UpdateHandler;
 
 pub mod error;
 pub mod update_handler;
 
 mod dump;
+mod nested;
 mod search;
 mod updates;
 
 pub type Document = Map<String, Value>;
 
+/// Name of the file, in the directory of an index, holding the size of its LMDB map when it
+/// differs from the default size.
+const MAP_SIZE_FILE_NAME: &str = "map_size";
+
+/// Key of the user metadata in the main database of an index.
+const USER_METADATA_KEY: &str = "user-metadata";
+
+/// Key of the display name in the main database of an index.
+const NAME_KEY: &str = "display-name";
+
+/// Keys of the creation and update dates in the main database of an index, as written by milli.
+const CREATED_AT_KEY: &str = "created-at";
+const UPDATED_AT_KEY: &str = "updated-at";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct IndexMeta {
+    /// The display name of the index. Unlike its uid, it isn't used to identify the index.
+    #[serde(default)]
+    pub name: Option<String>,
     created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub primary_key: Option<String>,
+    #[serde(default)]
+    pub metadata: IndexUserMetadata,
+}
+
+/// Metadata attached to an index by the user, it isn't used by the engine.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct IndexUserMetadata {
+    pub owner: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub labels: Map<String, Value>,
+}
+
+/// The changes to the primary key, the user metadata and the display name of an index, which
+/// are applied at once.
+#[derive(Debug, Default)]
+pub struct IndexMetaUpdate {
+    /// Sets the primary key of the index, which must not have one yet.
+    pub primary_key: Option<String>,
+    /// Replaces the user metadata of the index.
+    pub metadata: Option<IndexUserMetadata>,
+    /// Sets the display name of the index, or removes it when it is reset.
+    pub name: Setting<String>,
 }
 
 #[derive(Serialize, Debug)]
@@ -59,14 +103,18 @@ impl IndexMeta {
         Self::new_txn(index, &txn)
     }
 
-    fn new_txn(index: &Index, txn: &heed::RoTxn) -> Result<Self> {
+    pub(crate) fn new_txn(index: &Index, txn: &heed::RoTxn) -> Result<Self> {
         let created_at = index.created_at(txn)?;
         let updated_at = index.updated_at(txn)?;
         let primary_key = index.primary_key(txn)?.map(String::from);
+        let metadata = index.user_metadata(txn)?;
+        let name = index.display_name(txn)?;
         Ok(Self {
+            name,
             created_at,
             updated_at,
             primary_key,
+            metadata,
         })
     }
 }
@@ -81,6 +129,8 @@ pub struct Index {
     update_file_store: Arc<UpdateFileStore>,
     #[derivative(Debug = "ignore")]
     update_handler: Arc<UpdateHandler>,
+    /// The size of the LMDB map of the index, if it differs from the default size.
+    pub map_size: Option<usize>,
 }
 
 impl Deref for Index {
@@ -100,17 +150,52 @@ impl Index {
         update_handler: Arc<UpdateHandler>,
     ) -> Result<Self> {
         create_dir_all(&path)?;
+        let map_size = Self::persisted_map_size(&path)?;
         let mut options = EnvOpenOptions::new();
-        options.map_size(size);
+        options.map_size(map_size.unwrap_or(size));
         let inner = Arc::new(milli::Index::new(options, &path)?);
         Ok(Index {
             inner,
             update_file_store,
             uuid,
             update_handler,
+            map_size,
+        })
+    }
+
+    /// Flattens the nested fields of the documents stored before they were flattened. This is a
+    /// one time migration, which reindexes the documents having nested fields.
+    pub fn flatten_stored_documents(&self) -> Result<()> {
+        let uuid = self.uuid;
+        nested::flatten_stored_documents(&self.inner, |step| {
+            info!("flattening the nested fields of index {}: {:?}", uuid, step)
         })
     }
 
+    /// Returns the map size of the index in the directory `path`, if it differs from the
+    /// default size.
+    pub fn persisted_map_size(path: impl AsRef<Path>) -> Result<Option<usize>> {
+        let path = path.as_ref().join(MAP_SIZE_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let size = std::fs::read_to_string(path)?;
+        let size = size
+            .trim()
+            .parse()
+            .map_err(|e| IndexError::Internal(Box::new(e)))?;
+        Ok(Some(size))
+    }
+
+    /// Sets the map size of the index in the directory `path`, it is used the next time the
+    /// index is opened.
+    pub fn persist_map_size(path: impl AsRef<Path>, size: usize) -> Result<()> {
+        create_dir_all(&path)?;
+        std::fs::write(path.as_ref().join(MAP_SIZE_FILE_NAME), size.to_string())?;
+        Ok(())
+    }
+
     pub fn stats(&self) -> Result<IndexStats> {
         let rtxn = self.read_txn()?;
 
@@ -125,6 +210,58 @@ impl Index {
     pub fn meta(&self) -> Result<IndexMeta> {
         IndexMeta::new(self)
     }
+
+    pub fn user_metadata(&self, txn: &RoTxn) -> Result<IndexUserMetadata> {
+        let metadata = self
+            .main
+            .get::<_, Str, SerdeJson<IndexUserMetadata>>(txn, USER_METADATA_KEY)?
+            .unwrap_or_default();
+        Ok(metadata)
+    }
+
+    pub fn put_user_metadata(&self, txn: &mut RwTxn, metadata: &IndexUserMetadata) -> Result<()> {
+        self.main
+            .put::<_, Str, SerdeJson<IndexUserMetadata>>(txn, USER_METADATA_KEY, metadata)?;
+        Ok(())
+    }
+
+    pub fn display_name(&self, txn: &RoTxn) -> Result<Option<String>> {
+        let name = self.main.get::<_, Str, Str>(txn, NAME_KEY)?;
+        Ok(name.map(String::from))
+    }
+
+    pub fn put_display_name(&self, txn: &mut RwTxn, name: Option<&str>) -> Result<()> {
+        match name {
+            Some(name) => self.main.put::<_, Str, Str>(txn, NAME_KEY, name)?,
+            None => {
+                self.main.delete::<_, Str>(txn, NAME_KEY)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the update date of the index to now, for the changes that don't go through milli.
+    pub fn touch_updated_at(&self, txn: &mut RwTxn) -> Result<()> {
+        self.main
+            .put::<_, Str, SerdeJson<DateTime<Utc>>>(txn, UPDATED_AT_KEY, &Utc::now())?;
+        Ok(())
+    }
+
+    /// Removes the user metadata and the display name of the index, and sets its creation date
+    /// to now. A clone of an index only keeps its documents, primary key and settings.
+    pub fn reset_clone_metadata(&self) -> Result<()> {
+        let mut txn = self.write_txn()?;
+        self.main.delete::<_, Str>(&mut txn, USER_METADATA_KEY)?;
+        self.put_display_name(&mut txn, None)?;
+        let now = Utc::now();
+        self.main
+            .put::<_, Str, SerdeJson<DateTime<Utc>>>(&mut txn, CREATED_AT_KEY, &now)?;
+        self.main
+            .put::<_, Str, SerdeJson<DateTime<Utc>>>(&mut txn, UPDATED_AT_KEY, &now)?;
+        txn.commit()?;
+        Ok(())
+    }
+
     pub fn settings(&self) -> Result<Settings<Checked>> {
         let txn = self.read_txn()?;
         self.settings_txn(&txn)
@@ -135,13 +272,12 @@ impl Index {
             .displayed_fields(txn)?
             .map(|fields| fields.into_iter().map(String::from).collect());
 
-        let searchable_attributes = self
-            .searchable_fields(txn)?
-            .map(|fields| fields.into_iter().map(String::from).collect());
-
-        let filterable_attributes = self.filterable_fields(txn)?.into_iter().collect();
-
-        let sortable_attributes = self.sortable_fields(txn)?.into_iter().collect();
+        // The attributes are returned as declared, not expanded to their nested fields.
+        let DeclaredAttributes {
+            searchable: searchable_attributes,
+            filterable: filterable_attributes,
+            sortable: sortable_attributes,
+        } = DeclaredAttributes::get(self, txn)?;
 
         let criteria = self
             .criteria(txn)?
@@ -204,6 +340,7 @@ impl Index {
         let fields_ids_map = self.fields_ids_map(&txn)?;
         let fields_to_display =
             self.fields_to_display(&txn, &attributes_to_retrieve, &fields_ids_map)?;
+        let nested_fields = NestedFields::get(self, &txn)?;
 
         let iter = self.documents.range(&txn, &(..))?.skip(offset).take(limit);
 
@@ -212,7 +349,7 @@ impl Index {
         for entry in iter {
             let (_id, obkv) = entry?;
             let object = obkv_to_json(&fields_to_display, &fields_ids_map, obkv)?;
-            documents.push(object);
+            documents.push(unflatten_document(object, &nested_fields));
         }
 
         Ok(documents)
@@ -243,8 +380,9 @@ impl Index {
             .ok_or(IndexError::DocumentNotFound(doc_id))?;
 
         let document = obkv_to_json(&fields_to_display, &fields_ids_map, document)?;
+        let nested_fields = NestedFields::get(self, &txn)?;
 
-        Ok(document)
+        Ok(unflatten_document(document, &nested_fields))
     }
 
     pub fn size(&self) -> u64 {
@@ -257,15 +395,14 @@ impl Index {
         attributes_to_retrieve: &Option<Vec<S>>,
         fields_ids_map: &milli::FieldsIdsMap,
     ) -> Result<Vec<FieldId>> {
-        let mut displayed_fields_ids = match self.displayed_fields_ids(txn)? {
-            Some(ids) => ids.into_iter().collect::<Vec<_>>(),
+        let mut displayed_fields_ids = match self.displayed_fields(txn)? {
+            Some(fields) => nested::nested_fields_ids(fields_ids_map, &fields),
             None => fields_ids_map.iter().map(|(id, _)| id).collect(),
         };
 
         let attributes_to_retrieve_ids = match attributes_to_retrieve {
-            Some(attrs) => attrs
-                .iter()
-                .filter_map(|f| fields_ids_map.id(f.as_ref()))
+            Some(attrs) => nested::nested_fields_ids(fields_ids_map, attrs)
+                .into_iter()
                 .collect::<HashSet<_>>(),
             None => fields_ids_map.iter().map(|(id, _)| id).collect(),
         };
@@ -274,9 +411,22 @@ impl Index {
         Ok(displayed_fields_ids)
     }
 
+    /// Closes the environment of the index, once every other reference to the index is dropped.
+    pub fn prepare_for_closing(self) -> heed::EnvClosingEvent {
+        self.inner.env.clone().prepare_for_closing()
+    }
+
     pub fn snapshot(&self, path: impl AsRef<Path>) -> Result<()> {
-        let mut dst = path.as_ref().join(format!("indexes/{}/", self.uuid));
+        self.copy_to_path(path.as_ref().join(format!("indexes/{}/", self.uuid)))
+    }
+
+    /// Copies the whole LMDB environment of the index to the directory `path`.
+    pub fn copy_to_path(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut dst = path.as_ref().to_owned();
         create_dir_all(&dst)?;
+        if let Some(map_size) = self.map_size {
+            Self::persist_map_size(&dst, map_size)?;
+        }
         dst.push("data.mdb");
         let _txn = self.write_txn()?;
         self.inner
@@ -0,0 +1,557 @@
+//! Nested fields are stored flattened in the index: each leaf of an object is stored in its own
+//! field, named after its dotted path (`{"author": {"name": "x"}}` is stored as
+//! `{"author.name": "x"}`). This makes the nested fields usable as filterable, sortable and
+//! searchable attributes. The documents are flattened when they are indexed, and the fields
+//! produced by the flattening are recorded, so that only these fields are rebuilt into objects
+//! when the documents are returned: a field whose name contains a dot is otherwise kept as is.
+//!
+//! An attribute holding an object designates all its nested fields in the settings, so the
+//! attributes declared by the user are stored as is and expanded to their nested fields when the
+//! settings are updated. Since changing the attributes of milli reindexes all the documents, they
+//! are not expanded again when documents bring new nested fields to an index, unless the index is
+//! still empty: the settings must be updated again for these fields to be taken into account.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::mem::discriminant;
+
+use heed::types::{SerdeJson, Str, Unit};
+use heed::{RoTxn, RwTxn};
+use log::info;
+use milli::documents::{DocumentBatchBuilder, DocumentBatchReader};
+use milli::update::{IndexDocumentsMethod, Setting, UpdateBuilder, UpdateIndexingStep};
+use milli::{obkv_to_json, FieldId, FieldsIdsMap};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::error::Result;
+use super::{Checked, Settings};
+
+/// The geo field must be kept as an object for milli to find the `lat` and `lng` fields in it.
+const GEO_FIELD: &str = "_geo";
+
+/// Key of the attributes declared in the settings, in the main database of an index.
+const DECLARED_ATTRIBUTES_KEY: &str = "declared-attributes";
+
+/// Key set in the main database of an index once its documents are stored flattened.
+const FLATTENED_DOCUMENTS_KEY: &str = "flattened-documents";
+
+/// Key of the fields produced by flattening the documents, in the main database of an index.
+const NESTED_FIELDS_KEY: &str = "nested-fields";
+
+/// The fields produced by flattening the documents of an index, with the keys they were
+/// flattened from (`author.name` is produced from `["author", "name"]`).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NestedFields(BTreeMap<String, Vec<String>>);
+
+impl NestedFields {
+    /// Returns the nested fields recorded in `index`.
+    pub fn get(index: &milli::Index, txn: &RoTxn) -> Result<Self> {
+        Ok(index
+            .main
+            .get::<_, Str, SerdeJson<Self>>(txn, NESTED_FIELDS_KEY)?
+            .unwrap_or_default())
+    }
+
+    pub fn put(&self, index: &milli::Index, txn: &mut RwTxn) -> Result<()> {
+        index
+            .main
+            .put::<_, Str, SerdeJson<Self>>(txn, NESTED_FIELDS_KEY, self)?;
+        Ok(())
+    }
+}
+
+/// The searchable, filterable and sortable attributes as declared in the settings, before they
+/// are expanded to their nested fields.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeclaredAttributes {
+    pub searchable: Option<Vec<String>>,
+    pub filterable: BTreeSet<String>,
+    pub sortable: BTreeSet<String>,
+}
+
+impl DeclaredAttributes {
+    /// Returns the attributes declared in the settings of `index`. The indexes whose declared
+    /// attributes were never stored declare the attributes of their milli settings.
+    pub fn get(index: &milli::Index, txn: &RoTxn) -> Result<Self> {
+        match index
+            .main
+            .get::<_, Str, SerdeJson<Self>>(txn, DECLARED_ATTRIBUTES_KEY)?
+        {
+            Some(declared) => Ok(declared),
+            None => Self::applied(index, txn),
+        }
+    }
+
+    /// Returns the attributes of the milli settings of `index`.
+    fn applied(index: &milli::Index, txn: &RoTxn) -> Result<Self> {
+        Ok(Self {
+            searchable: index
+                .searchable_fields(txn)?
+                .map(|fields| fields.into_iter().map(String::from).collect()),
+            filterable: index.filterable_fields(txn)?.into_iter().collect(),
+            sortable: index.sortable_fields(txn)?.into_iter().collect(),
+        })
+    }
+
+    fn put(&self, index: &milli::Index, txn: &mut RwTxn) -> Result<()> {
+        index
+            .main
+            .put::<_, Str, SerdeJson<Self>>(txn, DECLARED_ATTRIBUTES_KEY, self)?;
+        Ok(())
+    }
+
+    /// Returns the attributes expanded to the nested fields of `fields_ids_map`.
+    fn expand(&self, fields_ids_map: &FieldsIdsMap) -> Self {
+        Self {
+            searchable: self
+                .searchable
+                .as_ref()
+                .map(|attributes| expand_attributes(fields_ids_map, attributes)),
+            filterable: expand_attributes(fields_ids_map, &self.filterable)
+                .into_iter()
+                .collect(),
+            sortable: expand_attributes(fields_ids_map, &self.sortable)
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/// Returns `attributes` followed by their nested fields in `fields_ids_map`, without duplicates.
+fn expand_attributes<'a>(
+    fields_ids_map: &FieldsIdsMap,
+    attributes: impl IntoIterator<Item = &'a String>,
+) -> Vec<String> {
+    let mut expanded: Vec<String> = Vec::new();
+    for attribute in attributes {
+        let nested = fields_ids_map
+            .iter()
+            .map(|(_, name)| name)
+            .filter(|name| is_nested_in(name, attribute));
+        for name in std::iter::once(attribute.as_str()).chain(nested) {
+            if !expanded.iter().any(|field| field == name) {
+                expanded.push(name.to_string());
+            }
+        }
+    }
+    expanded
+}
+
+/// Records the searchable, filterable and sortable attributes set by `settings`, and returns the
+/// settings to apply to milli, in which these attributes are expanded to their nested fields.
+pub fn expand_settings(
+    index: &milli::Index,
+    txn: &mut RwTxn,
+    settings: &Settings<Checked>,
+) -> Result<Settings<Checked>> {
+    let mut declared = DeclaredAttributes::get(index, txn)?;
+    match settings.searchable_attributes {
+        Setting::Set(ref attributes) => declared.searchable = Some(attributes.clone()),
+        Setting::Reset => declared.searchable = None,
+        Setting::NotSet => (),
+    }
+    match settings.filterable_attributes {
+        Setting::Set(ref attributes) => declared.filterable = attributes.clone(),
+        Setting::Reset => declared.filterable.clear(),
+        Setting::NotSet => (),
+    }
+    match settings.sortable_attributes {
+        Setting::Set(ref attributes) => declared.sortable = attributes.clone(),
+        Setting::Reset => declared.sortable.clear(),
+        Setting::NotSet => (),
+    }
+    declared.put(index, txn)?;
+
+    let expanded = declared.expand(&index.fields_ids_map(txn)?);
+    let mut settings = settings.clone();
+    if let (Setting::Set(attributes), Some(expanded)) =
+        (&mut settings.searchable_attributes, expanded.searchable)
+    {
+        *attributes = expanded;
+    }
+    if let Setting::Set(ref mut attributes) = settings.filterable_attributes {
+        *attributes = expanded.filterable;
+    }
+    if let Setting::Set(ref mut attributes) = settings.sortable_attributes {
+        *attributes = expanded.sortable;
+    }
+    Ok(settings)
+}
+
+/// Expands the declared attributes of an empty index to the nested fields of the documents about
+/// to be indexed, which must have been flattened already. The settings of an index holding
+/// documents are left untouched, since updating them would reindex all its documents.
+pub fn expand_settings_before_indexing<'a>(
+    index: &'a milli::Index,
+    txn: &mut RwTxn<'a, '_>,
+) -> Result<()> {
+    if index.number_of_documents(txn)? > 0 {
+        return Ok(());
+    }
+    let mut fields_ids_map = index.fields_ids_map(txn)?;
+    for name in NestedFields::get(index, txn)?.0.keys() {
+        fields_ids_map.insert(name);
+    }
+    apply_declared_attributes(index, txn, &fields_ids_map)
+}
+
+/// Expands the declared attributes to the nested fields of `fields_ids_map`. The milli settings
+/// are only updated if some of their attributes gained new nested fields.
+fn apply_declared_attributes<'a>(
+    index: &'a milli::Index,
+    txn: &mut RwTxn<'a, '_>,
+    fields_ids_map: &FieldsIdsMap,
+) -> Result<()> {
+    let declared = match index
+        .main
+        .get::<_, Str, SerdeJson<DeclaredAttributes>>(txn, DECLARED_ATTRIBUTES_KEY)?
+    {
+        Some(declared) => declared,
+        None => return Ok(()),
+    };
+    let expanded = declared.expand(fields_ids_map);
+    let applied = DeclaredAttributes::applied(index, txn)?;
+    if expanded == applied {
+        return Ok(());
+    }
+
+    let mut builder = UpdateBuilder::new(0).settings(txn, index);
+    if expanded.searchable != applied.searchable {
+        match expanded.searchable {
+            Some(fields) => builder.set_searchable_fields(fields),
+            None => builder.reset_searchable_fields(),
+        }
+    }
+    if expanded.filterable != applied.filterable {
+        builder.set_filterable_fields(expanded.filterable.into_iter().collect());
+    }
+    if expanded.sortable != applied.sortable {
+        builder.set_sortable_fields(expanded.sortable.into_iter().collect());
+    }
+    builder.execute(|_, _| ())?;
+
+    Ok(())
+}
+
+/// Flattens the documents stored before the nested fields were flattened, and expands the
+/// attributes of their settings to the new nested fields. This is only done once per index, and
+/// `progress` is called each time the reindexing moves on to another step.
+pub fn flatten_stored_documents(
+    index: &milli::Index,
+    progress: impl Fn(&UpdateIndexingStep) + Sync,
+) -> Result<()> {
+    let txn = index.read_txn()?;
+    if index
+        .main
+        .get::<_, Str, Unit>(&txn, FLATTENED_DOCUMENTS_KEY)?
+        .is_some()
+    {
+        return Ok(());
+    }
+    drop(txn);
+
+    let mut txn = index.write_txn()?;
+    let fields_ids_map = index.fields_ids_map(&txn)?;
+    let all_fields: Vec<_> = fields_ids_map.iter().map(|(id, _)| id).collect();
+    let mut nested_fields = NestedFields::get(index, &txn)?;
+
+    let mut documents = tempfile::tempfile()?;
+    let mut builder = DocumentBatchBuilder::new(&mut documents)?;
+    let mut count = 0;
+    for entry in index.documents.range(&txn, &(..))? {
+        let (_id, obkv) = entry?;
+        let document = obkv_to_json(&all_fields, &fields_ids_map, obkv)?;
+        let has_nested_fields = document.iter().any(|(key, value)| {
+            key != GEO_FIELD && matches!(value, Value::Object(object) if !object.is_empty())
+        });
+        if has_nested_fields {
+            builder.add_documents(&flatten_document(document, &mut nested_fields))?;
+            count += 1;
+        }
+    }
+    builder.finish()?;
+
+    if count > 0 {
+        info!("flattening the nested fields of {} documents", count);
+        documents.seek(SeekFrom::Start(0))?;
+        let reader = DocumentBatchReader::from_reader(documents)?;
+        let step = Mutex::new(None);
+        let mut builder = UpdateBuilder::new(0).index_documents(&mut txn, index);
+        builder.index_documents_method(IndexDocumentsMethod::ReplaceDocuments);
+        builder.execute(reader, |indexing_step, _| {
+            let mut step = step.lock();
+            if *step != Some(discriminant(&indexing_step)) {
+                *step = Some(discriminant(&indexing_step));
+                progress(&indexing_step);
+            }
+        })?;
+    }
+
+    nested_fields.put(index, &mut txn)?;
+    DeclaredAttributes::get(index, &txn)?.put(index, &mut txn)?;
+    let fields_ids_map = index.fields_ids_map(&txn)?;
+    apply_declared_attributes(index, &mut txn, &fields_ids_map)?;
+    index
+        .main
+        .put::<_, Str, Unit>(&mut txn, FLATTENED_DOCUMENTS_KEY, &())?;
+    txn.commit()?;
+
+    Ok(())
+}
+
+/// Flattens the documents of the batch `content`, and records the nested fields they produce in
+/// `index`. Returns the batch of the flattened documents.
+pub fn flatten_batch(
+    index: &milli::Index,
+    txn: &mut RwTxn,
+    content: impl Read + Seek,
+) -> Result<File> {
+    let mut reader = DocumentBatchReader::from_reader(content)?;
+    let mut nested_fields = NestedFields::get(index, txn)?;
+
+    let mut documents = tempfile::tempfile()?;
+    let mut builder = DocumentBatchBuilder::new(&mut documents)?;
+
+    while let Some((fields, obkv)) = reader.next_document_with_index()? {
+        let mut document = Map::new();
+        for (field_id, content) in obkv.iter() {
+            if let Some(field_name) = fields.get_by_left(&field_id) {
+                document.insert(field_name.to_string(), serde_json::from_slice(content)?);
+            }
+        }
+        builder.add_documents(&flatten_document(document, &mut nested_fields))?;
+    }
+
+    builder.finish()?;
+    documents.seek(SeekFrom::Start(0))?;
+
+    nested_fields.put(index, txn)?;
+    // The documents of the index are flattened from now on, there is nothing left to migrate.
+    index
+        .main
+        .put::<_, Str, Unit>(txn, FLATTENED_DOCUMENTS_KEY, &())?;
+
+    Ok(documents)
+}
+
+/// Flattens the objects contained in the document into dotted fields, and records the fields
+/// produced in `nested_fields`. Arrays and empty objects are kept as is.
+pub fn flatten_document(
+    document: Map<String, Value>,
+    nested_fields: &mut NestedFields,
+) -> Map<String, Value> {
+    let mut flattened = Map::new();
+    for (key, value) in document {
+        if key == GEO_FIELD {
+            flattened.insert(key, value);
+        } else {
+            flatten_value(&mut flattened, nested_fields, &mut vec![key], value);
+        }
+    }
+    flattened
+}
+
+fn flatten_value(
+    flattened: &mut Map<String, Value>,
+    nested_fields: &mut NestedFields,
+    path: &mut Vec<String>,
+    value: Value,
+) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                path.push(key);
+                flatten_value(flattened, nested_fields, path, value);
+                path.pop();
+            }
+        }
+        value => {
+            let name = path.join(".");
+            if path.len() > 1 && !nested_fields.0.contains_key(&name) {
+                nested_fields.0.insert(name.clone(), path.clone());
+            }
+            flattened.insert(name, value);
+        }
+    }
+}
+
+/// Rebuilds the objects of a document from its fields produced by flattening. The other fields
+/// are returned as is, even if their name contains dots.
+pub fn unflatten_document(
+    document: impl IntoIterator<Item = (String, Value)>,
+    nested_fields: &NestedFields,
+) -> Map<String, Value> {
+    let mut unflattened = Map::new();
+    let mut nested = Vec::new();
+    for (key, value) in document {
+        match nested_fields.0.get(&key) {
+            Some(path) => nested.push((path, value)),
+            None => {
+                unflattened.insert(key, value);
+            }
+        }
+    }
+    // The nested fields are inserted last, so that they never replace another field.
+    for (path, value) in nested {
+        insert_path(&mut unflattened, path, value);
+    }
+    unflattened
+}
+
+fn insert_path(object: &mut Map<String, Value>, path: &[String], value: Value) {
+    match path {
+        // The field can only be nested if there is no other value at the head of its path.
+        [head, tail @ ..]
+            if !tail.is_empty() && matches!(object.get(head), None | Some(Value::Object(_))) =>
+        {
+            let entry = object
+                .entry(head.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(inner) = entry {
+                insert_path(inner, tail, value);
+            }
+        }
+        _ => {
+            object.insert(path.join("."), value);
+        }
+    }
+}
+
+/// Returns `true` if `field` is `attribute` itself or one of its nested fields.
+pub fn is_nested_in(field: &str, attribute: &str) -> bool {
+    field
+        .strip_prefix(attribute)
+        .map_or(false, |rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// Returns the ids of the fields designated by `attributes`, an attribute designating itself and
+/// all its nested fields. The ids are returned in the order of the attributes.
+pub fn nested_fields_ids<S: AsRef<str>>(
+    fields_ids_map: &FieldsIdsMap,
+    attributes: &[S],
+) -> Vec<FieldId> {
+    let mut ids = Vec::new();
+    for attribute in attributes {
+        for (id, name) in fields_ids_map.iter() {
+            if is_nested_in(name, attribute.as_ref()) && !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(object) => object,
+            _ => panic!("not an object"),
+        }
+    }
+
+    #[test]
+    fn flatten_nested_objects() {
+        let document = object(json!({
+            "id": 1,
+            "author": { "name": "Victor Hugo", "birth": { "year": 1802 } },
+            "tags": [{ "name": "classic" }],
+            "empty": {},
+            "_geo": { "lat": 1.0, "lng": 2.0 },
+        }));
+
+        let mut nested_fields = NestedFields::default();
+        let flattened = flatten_document(document, &mut nested_fields);
+
+        assert_eq!(
+            Value::Object(flattened),
+            json!({
+                "id": 1,
+                "author.name": "Victor Hugo",
+                "author.birth.year": 1802,
+                "tags": [{ "name": "classic" }],
+                "empty": {},
+                "_geo": { "lat": 1.0, "lng": 2.0 },
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&nested_fields).unwrap(),
+            json!({
+                "author.name": ["author", "name"],
+                "author.birth.year": ["author", "birth", "year"],
+            })
+        );
+    }
+
+    #[test]
+    fn unflatten_is_inverse_of_flatten() {
+        let document = object(json!({
+            "id": 1,
+            "author": { "name": "Victor Hugo", "birth": { "year": 1802 } },
+            "tags": [{ "name": "classic" }],
+        }));
+
+        let mut nested_fields = NestedFields::default();
+        let flattened = flatten_document(document.clone(), &mut nested_fields);
+        let unflattened = unflatten_document(flattened, &nested_fields);
+
+        assert_eq!(unflattened, document);
+    }
+
+    #[test]
+    fn unflatten_keeps_conflicting_fields() {
+        let mut nested_fields = NestedFields::default();
+        flatten_document(
+            object(json!({ "author": { "name": "Hugo" } })),
+            &mut nested_fields,
+        );
+        let document = object(json!({ "author": "Victor Hugo", "author.name": "Hugo" }));
+
+        let unflattened = unflatten_document(document.clone(), &nested_fields);
+
+        assert_eq!(unflattened, document);
+    }
+
+    #[test]
+    fn unflatten_keeps_dotted_fields() {
+        let mut nested_fields = NestedFields::default();
+        let document = object(json!({ "id": 1, "author.name": "Victor Hugo", "a": { "b.c": 1 } }));
+
+        let flattened = flatten_document(document.clone(), &mut nested_fields);
+        let unflattened = unflatten_document(flattened, &nested_fields);
+
+        assert_eq!(unflattened, document);
+    }
+
+    #[test]
+    fn expand_object_attributes() {
+        let mut fields_ids_map = FieldsIdsMap::new();
+        for field in ["id", "author.name", "author.birth.year", "authors", "title"] {
+            fields_ids_map.insert(field).unwrap();
+        }
+        let attributes = vec!["title".to_string(), "author".to_string()];
+
+        let expanded = expand_attributes(&fields_ids_map, &attributes);
+
+        assert_eq!(
+            expanded,
+            vec!["title", "author", "author.name", "author.birth.year"]
+        );
+    }
+
+    #[test]
+    fn nested_attributes() {
+        assert!(is_nested_in("author", "author"));
+        assert!(is_nested_in("author.name", "author"));
+        assert!(!is_nested_in("authors", "author"));
+        assert!(!is_nested_in("author", "author.name"));
+    }
+}
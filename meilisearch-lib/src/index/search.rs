@@ -15,6 +15,7 @@ use crate::index::error::FacetError;
 use crate::index::IndexError;
 
 use super::error::Result;
+use super::nested::{nested_fields_ids, unflatten_document, NestedFields};
 use super::Index;
 
 pub type Document = IndexMap<String, Value>;
@@ -129,8 +130,12 @@ impl Index {
         let fields_ids_map = self.fields_ids_map(&rtxn).unwrap();
 
         let displayed_ids = self
-            .displayed_fields_ids(&rtxn)?
-            .map(|fields| fields.into_iter().collect::<BTreeSet<_>>())
+            .displayed_fields(&rtxn)?
+            .map(|fields| {
+                nested_fields_ids(&fields_ids_map, &fields)
+                    .into_iter()
+                    .collect::<BTreeSet<_>>()
+            })
             .unwrap_or_else(|| fields_ids_map.iter().map(|(id, _)| id).collect());
 
         let fids = |attrs: &BTreeSet<String>| {
@@ -141,9 +146,7 @@ impl Index {
                     break;
                 }
 
-                if let Some(id) = fields_ids_map.id(attr) {
-                    ids.insert(id);
-                }
+                ids.extend(nested_fields_ids(&fields_ids_map, &[attr]));
             }
             ids
         };
@@ -189,6 +192,7 @@ impl Index {
         let mut documents = Vec::new();
 
         let documents_iter = self.documents(&rtxn, documents_ids)?;
+        let nested_fields = NestedFields::get(self, &rtxn)?;
 
         for (_id, obkv) in documents_iter {
             let mut document = make_document(&to_retrieve_ids, &fields_ids_map, obkv)?;
@@ -209,9 +213,15 @@ impl Index {
                 insert_geo_distance(sort, &mut document);
             }
 
+            // The matches are reported on the dotted fields, the nested objects are only rebuilt
+            // in the returned documents.
             let hit = SearchHit {
-                document,
-                formatted,
+                document: unflatten_document(document, &nested_fields)
+                    .into_iter()
+                    .collect(),
+                formatted: unflatten_document(formatted, &nested_fields)
+                    .into_iter()
+                    .collect(),
                 matches_info,
             };
             documents.push(hit);
@@ -367,7 +377,7 @@ fn add_highlight_to_formatted_options(
             break;
         }
 
-        if let Some(id) = fields_ids_map.id(attr) {
+        for id in nested_fields_ids(fields_ids_map, &[attr]) {
             if displayed_ids.contains(&id) {
                 formatted_options.insert(id, new_format);
             }
@@ -404,7 +414,7 @@ fn add_crop_to_formatted_options(
             }
         }
 
-        if let Some(id) = fields_ids_map.id(attr_name) {
+        for id in nested_fields_ids(fields_ids_map, &[attr_name]) {
             if displayed_ids.contains(&id) {
                 formatted_options
                     .entry(id)
@@ -643,7 +653,11 @@ impl<'a, A: AsRef<[u8]>> Formatter<'a, A> {
     }
 }
 
-fn parse_filter(facets: &Value, index: &Index, txn: &RoTxn) -> Result<Option<FilterCondition>> {
+pub(super) fn parse_filter(
+    facets: &Value,
+    index: &Index,
+    txn: &RoTxn,
+) -> Result<Option<FilterCondition>> {
     match facets {
         Value::String(expr) => {
             let condition = FilterCondition::from_str(txn, index, expr)?;
@@ -1,18 +1,46 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use log::{debug, info, trace};
-use milli::documents::DocumentBatchReader;
-use milli::update::{IndexDocumentsMethod, Setting, UpdateBuilder};
+use milli::documents::{DocumentBatchBuilder, DocumentBatchReader};
+use milli::obkv_to_json;
+use milli::update::{IndexDocumentsMethod, Setting, UpdateBuilder, UpdateIndexingStep};
+use rhai::{Dynamic, Engine, Scope, AST};
 use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
 use uuid::Uuid;
 
-use crate::index_controller::updates::status::{Failed, Processed, Processing, UpdateResult};
+use crate::index_controller::updates::status::{
+    DocumentAdditionResult, Failed, Processed, Processing, UpdateResult,
+};
+use crate::index_controller::validation::{
+    infer_primary_key, is_valid_document_id, validate_document, InvalidDocument,
+    MAX_REPORTED_ERRORS,
+};
 use crate::Update;
 
 use super::error::{IndexError, Result};
-use super::{Index, IndexMeta};
+use super::nested::{
+    expand_settings, expand_settings_before_indexing, flatten_batch, flatten_document,
+    unflatten_document, NestedFields,
+};
+use super::search::parse_filter;
+use super::{Document, Index, IndexMeta, IndexMetaUpdate};
+
+/// Maximum number of operations an edition function is allowed to perform on a single document.
+const MAX_EDITION_OPERATIONS: u64 = 1_000_000;
+/// Maximum number of operations an edition function is allowed to perform over a whole update.
+const MAX_EDITION_TOTAL_OPERATIONS: u64 = 100_000_000;
+/// Maximum depth of nested function calls in an edition function.
+const MAX_EDITION_CALL_LEVELS: usize = 32;
+/// Maximum number of document ids listed in the report of a failed primary key change.
+const MAX_REPORTED_DOCUMENTS: usize = 10;
 
 fn serialize_with_wildcard<S>(
     field: &Setting<Vec<String>>,
@@ -164,7 +192,12 @@ pub struct Facets {
 }
 
 impl Index {
-    pub fn handle_update(&self, update: Processing) -> std::result::Result<Processed, Failed> {
+    /// Processes the `update`, `progress` is called with each step of its indexing.
+    pub fn handle_update(
+        &self,
+        update: Processing,
+        progress: &(dyn Fn(UpdateIndexingStep) + Sync),
+    ) -> std::result::Result<Processed, Failed> {
         let update_id = update.id();
         let update_builder = self.update_handler.update_builder(update_id);
         let result = (|| {
@@ -174,16 +207,21 @@ impl Index {
                     primary_key,
                     content_uuid,
                     method,
+                    version_field,
+                    lenient,
                 } => self.update_documents(
                     &mut txn,
                     *method,
                     *content_uuid,
                     update_builder,
                     primary_key.as_deref(),
+                    version_field.as_deref(),
+                    *lenient,
+                    progress,
                 ),
                 Update::Settings(settings) => {
                     let settings = settings.clone().check();
-                    self.update_settings(&mut txn, &settings, update_builder)
+                    self.update_settings(&mut txn, &settings, update_builder, progress)
                 }
                 Update::ClearDocuments => {
                     let builder = update_builder.clear_documents(&mut txn, self);
@@ -201,6 +239,16 @@ impl Index {
                     let deleted = builder.execute()?;
                     Ok(UpdateResult::DocumentDeletion { deleted })
                 }
+                Update::DocumentEdition { function, filter } => self.edit_documents(
+                    &mut txn,
+                    function,
+                    filter.as_ref(),
+                    update_builder,
+                    progress,
+                ),
+                Update::PrimaryKeyChange { primary_key } => {
+                    self.change_primary_key(&mut txn, primary_key, update_builder, progress)
+                }
             };
             if result.is_ok() {
                 txn.commit()?;
@@ -208,8 +256,13 @@ impl Index {
             result
         })();
 
+        // The content of an update that failed because the map is full is kept, since the
+        // update is retried once the map has grown.
+        let map_full = matches!(result, Err(ref e) if e.is_map_full());
         if let Update::DocumentAddition { content_uuid, .. } = update.from.meta() {
-            let _ = self.update_file_store.delete(*content_uuid);
+            if !map_full {
+                let _ = self.update_file_store.delete(*content_uuid);
+            }
         }
 
         match result {
@@ -218,25 +271,210 @@ impl Index {
         }
     }
 
-    pub fn update_primary_key(&self, primary_key: Option<String>) -> Result<IndexMeta> {
-        match primary_key {
-            Some(primary_key) => {
-                let mut txn = self.write_txn()?;
-                if self.primary_key(&txn)?.is_some() {
-                    return Err(IndexError::ExistingPrimaryKey);
+    /// Processes a batch of document additions with a single indexing. If it fails, the updates
+    /// are processed one by one instead, so that each of them gets the status it would have had
+    /// on its own.
+    pub fn handle_updates(
+        &self,
+        updates: Vec<Processing>,
+        progress: &(dyn Fn(UpdateIndexingStep) + Sync),
+    ) -> Vec<std::result::Result<Processed, Failed>> {
+        if updates.len() > 1 && self.can_batch(&updates[0]).unwrap_or(false) {
+            match self.add_documents_batch(&updates, progress) {
+                Ok(results) => {
+                    for update in &updates {
+                        if let Update::DocumentAddition { content_uuid, .. } = update.meta() {
+                            let _ = self.update_file_store.delete(*content_uuid);
+                        }
+                    }
+
+                    return updates
+                        .into_iter()
+                        .zip(results)
+                        .map(|(update, result)| Ok(update.process(result)))
+                        .collect();
                 }
-                let mut builder = UpdateBuilder::new(0).settings(&mut txn, self);
-                builder.set_primary_key(primary_key);
-                builder.execute(|_, _| ())?;
-                let meta = IndexMeta::new_txn(self, &txn)?;
-                txn.commit()?;
-                Ok(meta)
+                Err(e) => info!(
+                    "batched document addition failed, processing the updates one by one: {}",
+                    e
+                ),
+            }
+        }
+
+        updates
+            .into_iter()
+            .map(|update| self.handle_update(update, progress))
+            .collect()
+    }
+
+    /// Returns `true` if the document additions sharing the options of `update` can be indexed at
+    /// once. The lenient additions to an index without a primary key are not, since each of them
+    /// would infer its own primary key when discarding its invalid documents.
+    fn can_batch(&self, update: &Processing) -> Result<bool> {
+        match update.meta() {
+            Update::DocumentAddition {
+                primary_key,
+                lenient,
+                ..
+            } => Ok(!lenient
+                || primary_key.is_some()
+                || self.primary_key(&self.read_txn()?)?.is_some()),
+            _ => Ok(false),
+        }
+    }
+
+    /// Indexes the documents of all the `updates` at once, they must be document additions
+    /// sharing the same options. Returns the result of each update.
+    fn add_documents_batch(
+        &self,
+        updates: &[Processing],
+        progress: &(dyn Fn(UpdateIndexingStep) + Sync),
+    ) -> Result<Vec<UpdateResult>> {
+        let (primary_key, method, lenient) = match updates[0].meta() {
+            Update::DocumentAddition {
+                primary_key,
+                method,
+                lenient,
+                ..
+            } => (primary_key.as_deref(), *method, *lenient),
+            _ => {
+                return Err(IndexError::Internal(
+                    "only document additions can be batched".into(),
+                ))
+            }
+        };
+
+        trace!("performing a batch of {} document additions", updates.len());
+
+        let update_builder = self.update_handler.update_builder(updates[0].id());
+        let mut txn = self.write_txn()?;
+
+        // Set the primary key if not set already, ignore if already set.
+        if let (None, Some(primary_key)) = (self.primary_key(&txn)?, primary_key) {
+            let mut builder = UpdateBuilder::new(0).settings(&mut txn, self);
+            builder.set_primary_key(primary_key.to_string());
+            builder.execute(|_, _| ())?;
+        }
+
+        let mut documents = tempfile::tempfile()?;
+        let mut builder = DocumentBatchBuilder::new(&mut documents)?;
+        let mut document = Document::new();
+        let mut results = Vec::with_capacity(updates.len());
+
+        for update in updates {
+            let content_uuid = match update.meta() {
+                Update::DocumentAddition { content_uuid, .. } => *content_uuid,
+                _ => {
+                    return Err(IndexError::Internal(
+                        "only document additions can be batched".into(),
+                    ))
+                }
+            };
+
+            let content_file = self.update_file_store.get_update(content_uuid)?;
+            let content_file = flatten_batch(self, &mut txn, content_file)?;
+            let (content_file, nb_invalid_documents, invalid_documents) = if lenient {
+                self.discard_invalid_documents(&txn, content_file, primary_key)?
+            } else {
+                (content_file, 0, Vec::new())
+            };
+
+            let mut reader = DocumentBatchReader::from_reader(content_file)?;
+            let mut nb_documents = 0;
+            while let Some((index, obkv)) = reader.next_document_with_index()? {
+                document.clear();
+                for (field_id, content) in obkv.iter() {
+                    if let Some(field_name) = index.get_by_left(&field_id) {
+                        document.insert(field_name.to_string(), serde_json::from_slice(content)?);
+                    }
+                }
+                builder.add_documents(&document)?;
+                nb_documents += 1;
+            }
+
+            results.push(UpdateResult::DocumentsAddition(DocumentAdditionResult {
+                nb_documents,
+                nb_skipped_documents: None,
+                nb_invalid_documents,
+                invalid_documents,
+            }));
+        }
+
+        builder.finish()?;
+        documents.seek(SeekFrom::Start(0))?;
+        let reader = DocumentBatchReader::from_reader(documents)?;
+
+        let indexing_callback = |indexing_step, update_id| {
+            debug!("update {}: {:?}", update_id, indexing_step);
+            progress(indexing_step);
+        };
+
+        expand_settings_before_indexing(self, &mut txn)?;
+        let mut builder = update_builder.index_documents(&mut txn, self);
+        builder.index_documents_method(method);
+        let addition = builder.execute(reader, indexing_callback)?;
+
+        txn.commit()?;
+
+        info!("batched document addition done: {:?}", addition);
+
+        Ok(results)
+    }
+
+    /// Sets the primary key and the settings of an empty index at once.
+    pub fn init_settings(
+        &self,
+        primary_key: Option<String>,
+        settings: &Settings<Checked>,
+    ) -> Result<()> {
+        let mut txn = self.write_txn()?;
+        let settings = expand_settings(self, &mut txn, settings)?;
+        let mut builder = UpdateBuilder::new(0).settings(&mut txn, self);
+        if let Some(primary_key) = primary_key {
+            builder.set_primary_key(primary_key);
+        }
+        apply_settings_to_builder(&settings, &mut builder);
+        builder.execute(|_, _| ())?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Applies the changes to the primary key, the user metadata and the display name of the
+    /// index in a single transaction, so that they are either all applied or none is. The update
+    /// date of the index is set to now if anything changes.
+    pub fn update_meta(&self, update: IndexMetaUpdate) -> Result<IndexMeta> {
+        let mut txn = self.write_txn()?;
+        if let Some(primary_key) = update.primary_key {
+            if self.primary_key(&txn)?.is_some() {
+                return Err(IndexError::ExistingPrimaryKey);
             }
-            None => {
-                let meta = IndexMeta::new(self)?;
-                Ok(meta)
+            let mut builder = UpdateBuilder::new(0).settings(&mut txn, self);
+            builder.set_primary_key(primary_key);
+            builder.execute(|_, _| ())?;
+        }
+        let mut touched = false;
+        if let Some(metadata) = update.metadata {
+            self.put_user_metadata(&mut txn, &metadata)?;
+            touched = true;
+        }
+        match update.name {
+            Setting::Set(name) => {
+                self.put_display_name(&mut txn, Some(&name))?;
+                touched = true;
             }
+            Setting::Reset => {
+                self.put_display_name(&mut txn, None)?;
+                touched = true;
+            }
+            Setting::NotSet => (),
+        }
+        // The primary key change already updates the update date.
+        if touched {
+            self.touch_updated_at(&mut txn)?;
         }
+        let meta = IndexMeta::new_txn(self, &txn)?;
+        txn.commit()?;
+        Ok(meta)
     }
 
     fn update_documents<'a, 'b>(
@@ -246,6 +484,9 @@ impl Index {
         content_uuid: Uuid,
         update_builder: UpdateBuilder,
         primary_key: Option<&str>,
+        version_field: Option<&str>,
+        lenient: bool,
+        progress: &(dyn Fn(UpdateIndexingStep) + Sync),
     ) -> Result<UpdateResult> {
         trace!("performing document addition");
 
@@ -256,19 +497,367 @@ impl Index {
             builder.execute(|_, _| ())?;
         }
 
-        let indexing_callback =
-            |indexing_step, update_id| debug!("update {}: {:?}", update_id, indexing_step);
+        let indexing_callback = |indexing_step, update_id| {
+            debug!("update {}: {:?}", update_id, indexing_step);
+            progress(indexing_step);
+        };
 
         let content_file = self.update_file_store.get_update(content_uuid).unwrap();
+        let content_file = flatten_batch(self, txn, content_file)?;
+        let (content_file, nb_invalid_documents, invalid_documents) = if lenient {
+            self.discard_invalid_documents(txn, content_file, primary_key)?
+        } else {
+            (content_file, 0, Vec::new())
+        };
+        let (content_file, nb_skipped_documents) = match version_field {
+            Some(version_field) => {
+                let (content_file, skipped) =
+                    self.discard_outdated_documents(txn, content_file, version_field)?;
+                (content_file, Some(skipped))
+            }
+            None => (content_file, None),
+        };
         let reader = DocumentBatchReader::from_reader(content_file).unwrap();
 
+        expand_settings_before_indexing(self, txn)?;
         let mut builder = update_builder.index_documents(txn, self);
         builder.index_documents_method(method);
         let addition = builder.execute(reader, indexing_callback)?;
 
         info!("document addition done: {:?}", addition);
 
-        Ok(UpdateResult::DocumentsAddition(addition))
+        Ok(UpdateResult::DocumentsAddition(DocumentAdditionResult {
+            nb_documents: addition.nb_documents,
+            nb_skipped_documents,
+            nb_invalid_documents,
+            invalid_documents,
+        }))
+    }
+
+    /// Rewrites the documents batch without the documents that would make the whole addition
+    /// fail. Returns the new batch, the number of discarded documents and the first
+    /// `MAX_REPORTED_ERRORS` of them.
+    ///
+    /// The documents are checked against the primary key of the index, or the `primary_key`
+    /// given with the addition if the index has none yet, or else the inferred one, the same way
+    /// milli picks it.
+    fn discard_invalid_documents(
+        &self,
+        txn: &heed::RoTxn,
+        content_file: File,
+        primary_key: Option<&str>,
+    ) -> Result<(File, usize, Vec<InvalidDocument>)> {
+        let mut reader = DocumentBatchReader::from_reader(content_file)?;
+
+        let mut primary_key = self.primary_key(txn)?.or(primary_key).map(String::from);
+        let mut documents = tempfile::tempfile()?;
+        let mut builder = DocumentBatchBuilder::new(&mut documents)?;
+        let mut document = Document::new();
+        let mut nb_invalid_documents = 0;
+        let mut invalid_documents = Vec::new();
+        let mut position = 0;
+
+        while let Some((index, obkv)) = reader.next_document_with_index()? {
+            document.clear();
+            for (field_id, content) in obkv.iter() {
+                if let Some(field_name) = index.get_by_left(&field_id) {
+                    document.insert(field_name.to_string(), serde_json::from_slice(content)?);
+                }
+            }
+
+            if primary_key.is_none() {
+                primary_key = infer_primary_key(index.iter()).ok();
+            }
+
+            // Without a primary key every document is invalid, let the addition fail.
+            let result = match primary_key {
+                Some(ref primary_key) => validate_document(&document, primary_key)
+                    .map_err(|e| InvalidDocument::new(position, &document, primary_key, e)),
+                None => Ok(()),
+            };
+
+            match result {
+                Ok(()) => builder.add_documents(&document)?,
+                Err(invalid) => {
+                    nb_invalid_documents += 1;
+                    if invalid_documents.len() < MAX_REPORTED_ERRORS {
+                        invalid_documents.push(invalid);
+                    }
+                }
+            }
+
+            position += 1;
+        }
+
+        builder.finish()?;
+        documents.seek(SeekFrom::Start(0))?;
+
+        Ok((documents, nb_invalid_documents, invalid_documents))
+    }
+
+    /// Rewrites the documents batch without the documents whose `version_field` is lower than
+    /// the one of the stored document with the same id, or than the one of another document with
+    /// the same id in the batch. Among the documents of the batch sharing an id and the highest
+    /// version, the last one is kept. Returns the new batch and the number of discarded documents.
+    fn discard_outdated_documents(
+        &self,
+        txn: &heed::RoTxn,
+        mut content_file: File,
+        version_field: &str,
+    ) -> Result<(File, usize)> {
+        let mut primary_key = self.primary_key(txn)?.map(String::from);
+        let fields_ids_map = self.fields_ids_map(txn)?;
+        let version_fid = fields_ids_map.id(version_field);
+        let external_documents_ids = self.external_documents_ids(txn)?;
+
+        // The position of the document to keep for each id of the batch, with its version.
+        let mut latest: HashMap<String, (u64, usize)> = HashMap::new();
+        let mut reader = DocumentBatchReader::from_reader(content_file.try_clone()?)?;
+        let mut document = Document::new();
+        let mut position = 0;
+
+        while let Some((index, obkv)) = reader.next_document_with_index()? {
+            document.clear();
+            for (field_id, content) in obkv.iter() {
+                if let Some(field_name) = index.get_by_left(&field_id) {
+                    document.insert(field_name.to_string(), serde_json::from_slice(content)?);
+                }
+            }
+
+            // The index has no primary key yet, milli will infer it the same way.
+            if primary_key.is_none() {
+                primary_key = infer_primary_key(index.iter()).ok();
+            }
+            if let Some((id, version)) =
+                document_version(&document, primary_key.as_deref(), version_field)?
+            {
+                match latest.entry(id) {
+                    Entry::Occupied(entry) if entry.get().0 > version => (),
+                    Entry::Occupied(mut entry) => *entry.get_mut() = (version, position),
+                    Entry::Vacant(entry) => {
+                        entry.insert((version, position));
+                    }
+                }
+            }
+            position += 1;
+        }
+
+        content_file.seek(SeekFrom::Start(0))?;
+        let mut reader = DocumentBatchReader::from_reader(content_file)?;
+        let mut documents = tempfile::tempfile()?;
+        let mut builder = DocumentBatchBuilder::new(&mut documents)?;
+        let mut skipped = 0;
+        let mut position = 0;
+
+        while let Some((index, obkv)) = reader.next_document_with_index()? {
+            document.clear();
+            for (field_id, content) in obkv.iter() {
+                if let Some(field_name) = index.get_by_left(&field_id) {
+                    document.insert(field_name.to_string(), serde_json::from_slice(content)?);
+                }
+            }
+            let current = position;
+            position += 1;
+
+            let (id, version) =
+                match document_version(&document, primary_key.as_deref(), version_field)? {
+                    Some(id_and_version) => id_and_version,
+                    None => {
+                        builder.add_documents(&document)?;
+                        continue;
+                    }
+                };
+
+            if latest.get(&id).map_or(false, |(_, kept)| *kept != current) {
+                skipped += 1;
+                continue;
+            }
+
+            let stored_version = match (version_fid, external_documents_ids.get(&id)) {
+                (Some(version_fid), Some(docid)) => self
+                    .documents(txn, std::iter::once(docid))?
+                    .first()
+                    .and_then(|(_, obkv)| obkv.get(version_fid))
+                    .and_then(|content| serde_json::from_slice::<Value>(content).ok())
+                    .and_then(|version| version.as_u64()),
+                _ => None,
+            };
+
+            match stored_version {
+                Some(stored_version) if version < stored_version => skipped += 1,
+                _ => builder.add_documents(&document)?,
+            }
+        }
+
+        builder.finish()?;
+        documents.seek(SeekFrom::Start(0))?;
+
+        Ok((documents, skipped))
+    }
+
+    /// Applies the user provided `function` to every document matching `filter`, or to all the
+    /// documents if no filter is provided. The function reads and modifies the `doc` variable,
+    /// and only the documents that were actually modified are reindexed.
+    fn edit_documents<'a, 'b>(
+        &'a self,
+        txn: &mut heed::RwTxn<'a, 'b>,
+        function: &str,
+        filter: Option<&Value>,
+        update_builder: UpdateBuilder,
+        progress: &(dyn Fn(UpdateIndexingStep) + Sync),
+    ) -> Result<UpdateResult> {
+        trace!("performing document edition");
+
+        let engine = edition_engine(MAX_EDITION_TOTAL_OPERATIONS);
+        let ast = engine
+            .compile(function)
+            .map_err(|e| IndexError::InvalidEditionFunction(e.to_string()))?;
+
+        let condition = match filter {
+            Some(filter) => parse_filter(filter, self, txn)?,
+            None => None,
+        };
+        let candidates = match condition {
+            Some(condition) => condition.evaluate(txn, self)?,
+            None => self.documents_ids(txn)?,
+        };
+
+        let primary_key = self.primary_key(txn)?.map(String::from);
+        let fields_ids_map = self.fields_ids_map(txn)?;
+        let all_fields: Vec<_> = fields_ids_map.iter().map(|(id, _)| id).collect();
+        let mut nested_fields = NestedFields::get(self, txn)?;
+
+        let mut edited = 0;
+        let mut edited_documents = tempfile::tempfile()?;
+        let mut builder = DocumentBatchBuilder::new(&mut edited_documents)?;
+
+        for (_id, obkv) in self.documents(txn, candidates)? {
+            let document = obkv_to_json(&all_fields, &fields_ids_map, obkv)?;
+            if let Some(new_document) =
+                apply_edition_function(&engine, &ast, &document, &mut nested_fields)?
+            {
+                if let Some(ref primary_key) = primary_key {
+                    if new_document.get(primary_key) != document.get(primary_key) {
+                        let id = document
+                            .get(primary_key)
+                            .map(|id| id.to_string())
+                            .unwrap_or_default();
+                        return Err(IndexError::EditedPrimaryKey(id));
+                    }
+                }
+
+                builder.add_documents(&new_document)?;
+                edited += 1;
+            }
+        }
+
+        builder.finish()?;
+        nested_fields.put(self, txn)?;
+
+        if edited == 0 {
+            return Ok(UpdateResult::DocumentEdition { edited });
+        }
+
+        edited_documents.seek(SeekFrom::Start(0))?;
+        let reader = DocumentBatchReader::from_reader(edited_documents)?;
+
+        let indexing_callback = |indexing_step, update_id| {
+            debug!("update {}: {:?}", update_id, indexing_step);
+            progress(indexing_step);
+        };
+
+        let mut builder = update_builder.index_documents(txn, self);
+        builder.index_documents_method(IndexDocumentsMethod::ReplaceDocuments);
+        builder.execute(reader, indexing_callback)?;
+
+        info!("document edition done: {} documents edited", edited);
+
+        Ok(UpdateResult::DocumentEdition { edited })
+    }
+
+    /// Changes the primary key of the index, and reindexes all the documents with their new
+    /// ids. Fails without modifying the index if a document doesn't have the new primary key, or
+    /// if several documents have the same one.
+    fn change_primary_key<'a, 'b>(
+        &'a self,
+        txn: &mut heed::RwTxn<'a, 'b>,
+        primary_key: &str,
+        update_builder: UpdateBuilder,
+        progress: &(dyn Fn(UpdateIndexingStep) + Sync),
+    ) -> Result<UpdateResult> {
+        trace!("performing primary key change to {}", primary_key);
+
+        let old_primary_key = self.primary_key(txn)?.map(String::from);
+        let fields_ids_map = self.fields_ids_map(txn)?;
+        let all_fields: Vec<_> = fields_ids_map.iter().map(|(id, _)| id).collect();
+
+        let mut ids = HashSet::new();
+        let mut missing = Vec::new();
+        let mut invalid = Vec::new();
+        let mut duplicates = BTreeSet::new();
+        let mut count = 0;
+        let mut documents = tempfile::tempfile()?;
+        let mut builder = DocumentBatchBuilder::new(&mut documents)?;
+
+        for (_id, obkv) in self.documents(txn, self.documents_ids(txn)?)? {
+            let document = obkv_to_json(&all_fields, &fields_ids_map, obkv)?;
+            let old_id = || {
+                old_primary_key
+                    .as_ref()
+                    .and_then(|old_primary_key| document.get(old_primary_key))
+                    .map(external_id)
+                    .unwrap_or_default()
+            };
+            match document.get(primary_key).filter(|id| !id.is_null()) {
+                Some(id) if is_valid_document_id(id) => {
+                    let id = external_id(id);
+                    if !ids.insert(id.clone()) {
+                        duplicates.insert(id);
+                    }
+                }
+                Some(_) => invalid.push(old_id()),
+                None => missing.push(old_id()),
+            }
+
+            builder.add_documents(&document)?;
+            count += 1;
+        }
+
+        builder.finish()?;
+
+        if !missing.is_empty() || !invalid.is_empty() || !duplicates.is_empty() {
+            let report = primary_key_change_report(&missing, &invalid, &duplicates);
+            return Err(IndexError::InvalidPrimaryKeyChange(
+                primary_key.to_string(),
+                report,
+            ));
+        }
+
+        // milli only allows to change the primary key of an empty index, so the documents are
+        // cleared and added back, which rebuilds the external documents ids.
+        UpdateBuilder::new(0).clear_documents(txn, self).execute()?;
+
+        let mut builder = UpdateBuilder::new(0).settings(txn, self);
+        builder.set_primary_key(primary_key.to_string());
+        builder.execute(|_, _| ())?;
+
+        if count > 0 {
+            documents.seek(SeekFrom::Start(0))?;
+            let reader = DocumentBatchReader::from_reader(documents)?;
+
+            let indexing_callback = |indexing_step, update_id| {
+                debug!("update {}: {:?}", update_id, indexing_step);
+                progress(indexing_step);
+            };
+
+            let mut builder = update_builder.index_documents(txn, self);
+            builder.index_documents_method(IndexDocumentsMethod::ReplaceDocuments);
+            builder.execute(reader, indexing_callback)?;
+        }
+
+        info!("primary key change done: {} documents reindexed", count);
+
+        Ok(UpdateResult::PrimaryKeyChange { documents: count })
     }
 
     fn update_settings<'a, 'b>(
@@ -276,20 +865,147 @@ impl Index {
         txn: &mut heed::RwTxn<'a, 'b>,
         settings: &Settings<Checked>,
         update_builder: UpdateBuilder,
+        progress: &(dyn Fn(UpdateIndexingStep) + Sync),
     ) -> Result<UpdateResult> {
         // We must use the write transaction of the update here.
+        let settings = expand_settings(self, txn, settings)?;
         let mut builder = update_builder.settings(txn, self);
 
-        apply_settings_to_builder(settings, &mut builder);
+        apply_settings_to_builder(&settings, &mut builder);
 
         builder.execute(|indexing_step, update_id| {
-            debug!("update {}: {:?}", update_id, indexing_step)
+            debug!("update {}: {:?}", update_id, indexing_step);
+            progress(indexing_step);
         })?;
 
         Ok(UpdateResult::Other)
     }
 }
 
+/// Returns the id and the version of `document`, if it has both. Fails if the version isn't a
+/// positive integer.
+fn document_version(
+    document: &Document,
+    primary_key: Option<&str>,
+    version_field: &str,
+) -> Result<Option<(String, u64)>> {
+    let id = match primary_key.and_then(|pk| document.get(pk)) {
+        Some(Value::String(id)) => id.clone(),
+        Some(Value::Number(id)) => id.to_string(),
+        _ => return Ok(None),
+    };
+    match document.get(version_field) {
+        Some(version) => version
+            .as_u64()
+            .map(|version| Some((id.clone(), version)))
+            .ok_or(IndexError::InvalidDocumentVersion(id)),
+        None => Ok(None),
+    }
+}
+
+/// Returns the external id of a document from the value of its primary key.
+fn external_id(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+
+/// Describes why the documents can't be reindexed with a new primary key, listing at most
+/// `MAX_REPORTED_DOCUMENTS` documents for each reason.
+fn primary_key_change_report(
+    missing: &[String],
+    invalid: &[String],
+    duplicates: &BTreeSet<String>,
+) -> String {
+    let list = |ids: &mut dyn Iterator<Item = &String>, count: usize| {
+        let mut list: Vec<_> = ids
+            .take(MAX_REPORTED_DOCUMENTS)
+            .map(|id| format!("`{}`", id))
+            .collect();
+        if count > MAX_REPORTED_DOCUMENTS {
+            list.push(format!("and {} more", count - MAX_REPORTED_DOCUMENTS));
+        }
+        list.join(", ")
+    };
+
+    let mut reasons = Vec::new();
+    if !missing.is_empty() {
+        reasons.push(format!(
+            "{} documents don't have the field ({})",
+            missing.len(),
+            list(&mut missing.iter(), missing.len()),
+        ));
+    }
+    if !invalid.is_empty() {
+        reasons.push(format!(
+            "{} documents have a value that isn't a valid document id ({})",
+            invalid.len(),
+            list(&mut invalid.iter(), invalid.len()),
+        ));
+    }
+    if !duplicates.is_empty() {
+        reasons.push(format!(
+            "{} values are shared by several documents ({})",
+            duplicates.len(),
+            list(&mut duplicates.iter(), duplicates.len()),
+        ));
+    }
+
+    format!("{}.", reasons.join(", and "))
+}
+
+/// Creates the script engine used to run the document edition functions. Since the functions are
+/// user provided, the engine is restricted so that a function can't run forever, neither on a
+/// single document nor over all the documents of an update.
+fn edition_engine(max_total_operations: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_EDITION_OPERATIONS);
+    // The progress callback is called once per operation, across all the documents evaluated by
+    // this engine. Returning a value terminates the running function.
+    let total_operations = Arc::new(AtomicU64::new(0));
+    engine.on_progress(move |_| {
+        let total = total_operations.fetch_add(1, Ordering::Relaxed) + 1;
+        (total > max_total_operations).then(|| Dynamic::UNIT)
+    });
+    engine.set_max_call_levels(MAX_EDITION_CALL_LEVELS);
+    engine.disable_symbol("eval");
+    engine
+}
+
+/// Runs the edition function on `document`, and returns the new document if it was modified.
+/// The function sees the nested objects of the document, while both documents are flattened.
+fn apply_edition_function(
+    engine: &Engine,
+    ast: &AST,
+    document: &Document,
+    nested_fields: &mut NestedFields,
+) -> Result<Option<Document>> {
+    let edition_error = |e: Box<rhai::EvalAltResult>| match *e {
+        rhai::EvalAltResult::ErrorTerminated(..) => {
+            IndexError::EditionOperationsExceeded(MAX_EDITION_TOTAL_OPERATIONS)
+        }
+        e => IndexError::InvalidEditionFunction(e.to_string()),
+    };
+
+    let mut scope = Scope::new();
+    scope.push_dynamic(
+        "doc",
+        rhai::serde::to_dynamic(unflatten_document(document.clone(), nested_fields))
+            .map_err(edition_error)?,
+    );
+
+    engine
+        .eval_ast_with_scope::<Dynamic>(&mut scope, ast)
+        .map_err(edition_error)?;
+
+    let new_document = scope.get_value::<Dynamic>("doc").unwrap_or(Dynamic::UNIT);
+    let new_document: Document = rhai::serde::from_dynamic(&new_document).map_err(edition_error)?;
+    let new_document = flatten_document(new_document, nested_fields);
+
+    Ok((&new_document != document).then(|| new_document))
+}
+
 pub fn apply_settings_to_builder(
     settings: &Settings<Checked>,
     builder: &mut milli::update::Settings,
@@ -389,4 +1105,21 @@ mod test {
         assert_eq!(checked.displayed_attributes, Setting::Reset);
         assert_eq!(checked.searchable_attributes, Setting::Reset);
     }
+
+    #[test]
+    fn edition_operations_are_limited_over_the_whole_update() {
+        let engine = edition_engine(100);
+        let ast = engine.compile("for i in 0..20 { doc.count = i; }").unwrap();
+        let document: Document = serde_json::from_str(r#"{ "id": 1 }"#).unwrap();
+        let mut nested_fields = NestedFields::default();
+
+        // a single document stays under the budget, but the next ones exhaust it.
+        assert!(apply_edition_function(&engine, &ast, &document, &mut nested_fields).is_ok());
+        let error = (0..10)
+            .find_map(|_| {
+                apply_edition_function(&engine, &ast, &document, &mut nested_fields).err()
+            })
+            .unwrap();
+        assert!(matches!(error, IndexError::EditionOperationsExceeded(_)));
+    }
 }
@@ -176,6 +176,7 @@ impl From<compat::Processing> for Processing {
         Self {
             from: from.into(),
             started_processing_at,
+            progress: None,
         }
     }
 }
@@ -201,6 +202,8 @@ impl From<compat::Enqueued> for Enqueued {
                     // Just ignore if the uuid is no present. If it is needed later, an error will
                     // be thrown.
                     content_uuid: content.unwrap_or_else(Uuid::default),
+                    version_field: None,
+                    lenient: false,
                 }
             }
             compat::UpdateMeta::ClearDocuments => Update::ClearDocuments,
@@ -210,8 +213,11 @@ impl From<compat::Enqueued> for Enqueued {
 
         Self {
             update_id,
+            global_id: None,
             meta,
             enqueued_at,
+            priority: 0,
+            webhook: None,
         }
     }
 }
@@ -235,7 +241,7 @@ impl From<compat::Processed> for Processed {
 impl From<compat::UpdateResult> for UpdateResult {
     fn from(other: compat::UpdateResult) -> Self {
         match other {
-            compat::UpdateResult::DocumentsAddition(r) => Self::DocumentsAddition(r),
+            compat::UpdateResult::DocumentsAddition(r) => Self::DocumentsAddition(r.into()),
             compat::UpdateResult::DocumentDeletion { deleted } => {
                 Self::DocumentDeletion { deleted }
             }
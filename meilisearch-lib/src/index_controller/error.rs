@@ -1,14 +1,18 @@
 use std::error::Error;
+use std::time::Duration;
 
 use meilisearch_error::Code;
 use meilisearch_error::ErrorCode;
 use tokio::task::JoinError;
 
+use crate::document_formats::DocumentFormatError;
 use crate::index::error::IndexError;
 
 use super::dump_actor::error::DumpActorError;
 use super::index_resolver::error::IndexResolverError;
 use super::updates::error::UpdateLoopError;
+use super::validation::DocumentValidationError;
+use super::DocumentAdditionFormat;
 
 pub type Result<T> = std::result::Result<T, IndexControllerError>;
 
@@ -24,11 +28,24 @@ pub enum IndexControllerError {
     DumpActor(#[from] DumpActorError),
     #[error("{0}")]
     IndexError(#[from] IndexError),
+    #[error("{0}")]
+    DocumentFormatError(#[from] DocumentFormatError),
+    #[error("{0}")]
+    DocumentValidation(#[from] DocumentValidationError),
+    #[error("A {0} payload is missing.")]
+    MissingPayload(DocumentAdditionFormat),
+    #[error("The indexes could not be swapped, since their pending updates were not processed within {} seconds.", .0.as_secs())]
+    SwapTimeout(Duration),
     #[error("Internal error: {0}")]
     Internal(Box<dyn Error + Send + Sync + 'static>),
 }
 
-internal_error!(IndexControllerError: JoinError);
+internal_error!(
+    IndexControllerError: JoinError,
+    std::io::Error,
+    serde_json::Error,
+    milli::documents::Error
+);
 
 impl ErrorCode for IndexControllerError {
     fn error_code(&self) -> Code {
@@ -38,6 +55,10 @@ impl ErrorCode for IndexControllerError {
             IndexControllerError::UpdateLoop(e) => e.error_code(),
             IndexControllerError::DumpActor(e) => e.error_code(),
             IndexControllerError::IndexError(e) => e.error_code(),
+            IndexControllerError::DocumentFormatError(e) => e.error_code(),
+            IndexControllerError::DocumentValidation(e) => e.error_code(),
+            IndexControllerError::MissingPayload(_) => Code::MissingPayload,
+            IndexControllerError::SwapTimeout(_) => Code::IndexSwapTimeout,
             IndexControllerError::Internal(_) => Code::Internal,
         }
     }
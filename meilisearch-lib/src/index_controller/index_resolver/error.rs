@@ -24,6 +24,14 @@ pub enum IndexResolverError {
     Milli(#[from] milli::Error),
     #[error("Index must have a valid uid; Index uid can be of type integer or string only composed of alphanumeric characters, hyphens (-) and underscores (_).")]
     BadlyFormatted(String),
+    #[error("Index {0} can't be swapped more than once at the same time.")]
+    DuplicateSwappedIndex(String),
+    #[error("The map size of an index must be at least 1 MiB, found {0} bytes.")]
+    InvalidMapSize(usize),
+    #[error("Alias {0} not found")]
+    UnexistingAlias(String),
+    #[error("Alias must be valid; An alias can only be composed of alphanumeric characters, hyphens (-) and underscores (_), and can't be the uid of an index.")]
+    BadlyFormattedAlias(String),
 }
 
 impl<T> From<MpscSendError<T>> for IndexResolverError
@@ -59,6 +67,10 @@ impl ErrorCode for IndexResolverError {
             IndexResolverError::Internal(_) => Code::Internal,
             IndexResolverError::Milli(e) => MilliError(e).error_code(),
             IndexResolverError::BadlyFormatted(_) => Code::InvalidIndexUid,
+            IndexResolverError::DuplicateSwappedIndex(_) => Code::BadRequest,
+            IndexResolverError::InvalidMapSize(_) => Code::BadRequest,
+            IndexResolverError::UnexistingAlias(_) => Code::AliasNotFound,
+            IndexResolverError::BadlyFormattedAlias(_) => Code::InvalidAlias,
         }
     }
 }
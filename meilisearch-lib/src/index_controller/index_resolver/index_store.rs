@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+use heed::EnvClosingEvent;
+use log::{debug, info};
 use milli::update::UpdateBuilder;
 use tokio::fs;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
 use tokio::task::spawn_blocking;
 use uuid::Uuid;
 
@@ -14,17 +17,171 @@ use crate::index::Index;
 use crate::index_controller::update_file_store::UpdateFileStore;
 use crate::options::IndexerOpts;
 
-type AsyncMap<K, V> = Arc<RwLock<HashMap<K, V>>>;
+/// The opened indexes. When more than `max_open` indexes are opened, the least recently used ones
+/// are closed, and they are reopened the next time they are needed.
+#[derive(Default)]
+struct IndexCache {
+    indexes: HashMap<Uuid, CachedIndex>,
+    /// The opened indexes, ordered by the time they were last known to be used. `get` only
+    /// updates the time of use of an index, its entry here is moved lazily, on eviction.
+    lru: BTreeMap<u64, Uuid>,
+    /// The closing events of the closed indexes, since an index can't be reopened before its
+    /// environment is actually closed.
+    closing: HashMap<Uuid, EnvClosingEvent>,
+    /// The indexes being reopened with a bigger map, notified once they are reopened.
+    growing: HashMap<Uuid, Arc<Notify>>,
+    max_open: Option<usize>,
+    clock: AtomicU64,
+}
+
+struct CachedIndex {
+    index: Index,
+    last_used: AtomicU64,
+    /// The key of the index in `IndexCache::lru`.
+    queued_at: u64,
+}
+
+impl IndexCache {
+    fn new(max_open: Option<usize>) -> Self {
+        Self {
+            max_open,
+            ..Default::default()
+        }
+    }
+
+    fn contains(&self, uuid: &Uuid) -> bool {
+        self.indexes.contains_key(uuid)
+    }
+
+    /// Returns the index if it is opened, without counting it as used.
+    fn peek(&self, uuid: &Uuid) -> Option<Index> {
+        self.indexes.get(uuid).map(|cached| cached.index.clone())
+    }
+
+    fn get(&self, uuid: &Uuid) -> Option<Index> {
+        let cached = self.indexes.get(uuid)?;
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        cached.last_used.store(now, Ordering::Relaxed);
+        Some(cached.index.clone())
+    }
+
+    /// Inserts the opened `index`, and closes the least recently used indexes if there are too
+    /// many opened indexes.
+    fn insert(&mut self, uuid: Uuid, index: Index) {
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        let cached = CachedIndex {
+            index,
+            last_used: AtomicU64::new(now),
+            queued_at: now,
+        };
+        if let Some(previous) = self.indexes.insert(uuid, cached) {
+            self.lru.remove(&previous.queued_at);
+        }
+        self.lru.insert(now, uuid);
+
+        let max_open = match self.max_open {
+            Some(max_open) => max_open,
+            None => return,
+        };
+
+        // The indexes still used elsewhere, by an update or a search for example, can't be
+        // closed. They are put back in the queue afterwards, and closed later if there are still
+        // too many opened indexes.
+        let mut in_use = Vec::new();
+        while self.indexes.len() > max_open {
+            let (queued_at, id) = match pop_first(&mut self.lru) {
+                Some(entry) => entry,
+                None => break,
+            };
+            let cached = match self.indexes.get_mut(&id) {
+                Some(cached) => cached,
+                None => continue,
+            };
+
+            let last_used = cached.last_used.load(Ordering::Relaxed);
+            if last_used != queued_at {
+                // The index was used since it was queued, it takes its place in the queue.
+                cached.queued_at = last_used;
+                self.lru.insert(last_used, id);
+            } else if id == uuid || Arc::strong_count(&cached.index.inner) > 1 {
+                in_use.push((queued_at, id));
+            } else if let Some(cached) = self.indexes.remove(&id) {
+                debug!("closing index {}", id);
+                self.closing.insert(id, cached.index.prepare_for_closing());
+            }
+        }
+        self.lru.extend(in_use);
+    }
+
+    fn remove(&mut self, uuid: &Uuid) -> Option<Index> {
+        self.closing.remove(uuid);
+        let cached = self.indexes.remove(uuid)?;
+        self.lru.remove(&cached.queued_at);
+        Some(cached.index)
+    }
+
+    /// Returns the closing event of the index if it was closed to make room for other indexes.
+    fn take_closing_event(&mut self, uuid: &Uuid) -> Option<EnvClosingEvent> {
+        self.closing.remove(uuid)
+    }
+
+    /// Returns the notification sent once the index is reopened, if it is growing.
+    fn growing(&self, uuid: &Uuid) -> Option<Arc<Notify>> {
+        self.growing.get(uuid).cloned()
+    }
+
+    /// Closes the index and marks it as growing, until `end_growing` is called. Returns the
+    /// closing event of the index, to wait for before reopening it.
+    fn start_growing(&mut self, uuid: Uuid) -> Option<EnvClosingEvent> {
+        // An index closed to make room for other indexes must be waited for as well.
+        let closing = self.take_closing_event(&uuid);
+        let closing = match self.indexes.remove(&uuid) {
+            Some(cached) => {
+                self.lru.remove(&cached.queued_at);
+                Some(cached.index.prepare_for_closing())
+            }
+            None => closing,
+        };
+        self.growing.insert(uuid, Arc::new(Notify::new()));
+        closing
+    }
+
+    fn end_growing(&mut self, uuid: &Uuid) -> Option<Arc<Notify>> {
+        self.growing.remove(uuid)
+    }
+}
+
+/// Removes and returns the first entry of `map`.
+fn pop_first<K: Ord + Copy, V>(map: &mut BTreeMap<K, V>) -> Option<(K, V)> {
+    let key = *map.keys().next()?;
+    map.remove(&key).map(|value| (key, value))
+}
 
 #[async_trait::async_trait]
 pub trait IndexStore {
-    async fn create(&self, uuid: Uuid, primary_key: Option<String>) -> Result<Index>;
+    /// Creates the index `uuid`, with a map of `map_size` bytes instead of the default size if
+    /// it is given.
+    async fn create(
+        &self,
+        uuid: Uuid,
+        primary_key: Option<String>,
+        map_size: Option<usize>,
+    ) -> Result<Index>;
+    /// Creates the index `uuid` with the primary key and the settings of `src`, and its documents
+    /// if `copy_documents` is set.
+    async fn create_from(&self, uuid: Uuid, src: Index, copy_documents: bool) -> Result<Index>;
     async fn get(&self, uuid: Uuid) -> Result<Option<Index>>;
+    /// Returns the index `uuid` if it is opened, without opening it.
+    async fn get_opened(&self, uuid: Uuid) -> Result<Option<Index>>;
     async fn delete(&self, uuid: Uuid) -> Result<Option<Index>>;
+    /// Whether the index `uuid` exists, without opening it.
+    async fn exists(&self, uuid: Uuid) -> Result<bool>;
+    /// Doubles the size of the map of the index `uuid`, and returns the reopened index.
+    async fn grow(&self, uuid: Uuid) -> Result<Index>;
 }
 
 pub struct MapIndexStore {
-    index_store: AsyncMap<Uuid, Index>,
+    index_store: Arc<RwLock<IndexCache>>,
     path: PathBuf,
     index_size: usize,
     update_file_store: Arc<UpdateFileStore>,
@@ -35,12 +192,13 @@ impl MapIndexStore {
     pub fn new(
         path: impl AsRef<Path>,
         index_size: usize,
+        max_open_indexes: Option<usize>,
         indexer_opts: &IndexerOpts,
     ) -> anyhow::Result<Self> {
         let update_handler = Arc::new(UpdateHandler::new(indexer_opts)?);
         let update_file_store = Arc::new(UpdateFileStore::new(path.as_ref()).unwrap());
         let path = path.as_ref().join("indexes/");
-        let index_store = Arc::new(RwLock::new(HashMap::new()));
+        let index_store = Arc::new(RwLock::new(IndexCache::new(max_open_indexes)));
         Ok(Self {
             index_store,
             path,
@@ -49,17 +207,43 @@ impl MapIndexStore {
             update_handler,
         })
     }
+
+    /// Flattens the nested fields of the documents of the indexes `uuids` stored before they were
+    /// flattened. The indexes are opened one at a time, and closed once migrated.
+    pub fn flatten_stored_documents(&self, uuids: impl IntoIterator<Item = Uuid>) -> Result<()> {
+        for uuid in uuids {
+            let path = self.path.join(format!("{}", uuid));
+            if !path.exists() {
+                continue;
+            }
+            let index = Index::open(
+                path,
+                self.index_size,
+                self.update_file_store.clone(),
+                uuid,
+                self.update_handler.clone(),
+            )?;
+            index.flatten_stored_documents()?;
+            index.prepare_for_closing().wait();
+        }
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
 impl IndexStore for MapIndexStore {
-    async fn create(&self, uuid: Uuid, primary_key: Option<String>) -> Result<Index> {
+    async fn create(
+        &self,
+        uuid: Uuid,
+        primary_key: Option<String>,
+        map_size: Option<usize>,
+    ) -> Result<Index> {
         // We need to keep the lock until we are sure the db file has been opened correclty, to
         // ensure that another db is not created at the same time.
         let mut lock = self.index_store.write().await;
 
         if let Some(index) = lock.get(&uuid) {
-            return Ok(index.clone());
+            return Ok(index);
         }
         let path = self.path.join(format!("{}", uuid));
         if path.exists() {
@@ -70,6 +254,9 @@ impl IndexStore for MapIndexStore {
         let file_store = self.update_file_store.clone();
         let update_handler = self.update_handler.clone();
         let index = spawn_blocking(move || -> Result<Index> {
+            if let Some(map_size) = map_size {
+                Index::persist_map_size(&path, map_size)?;
+            }
             let index = Index::open(path, index_size, file_store, uuid, update_handler)?;
             if let Some(primary_key) = primary_key {
                 let mut txn = index.write_txn()?;
@@ -89,29 +276,107 @@ impl IndexStore for MapIndexStore {
         Ok(index)
     }
 
+    async fn create_from(&self, uuid: Uuid, src: Index, copy_documents: bool) -> Result<Index> {
+        let path = self.path.join(format!("{}", uuid));
+        if self.index_store.read().await.contains(&uuid) || path.exists() {
+            return Err(IndexResolverError::IndexAlreadyExists);
+        }
+
+        // The index is built in a temporary directory and moved in place without holding the
+        // lock on the opened indexes, since copying the documents of a big index takes a while.
+        // The uuid of the clone is not known by anyone else yet, so it can't be opened meanwhile.
+        let indexes_path = self.path.clone();
+        let index_size = self.index_size;
+        let file_store = self.update_file_store.clone();
+        let update_handler = self.update_handler.clone();
+        let index = spawn_blocking(move || -> Result<Index> {
+            std::fs::create_dir_all(&indexes_path)?;
+            let tmp = tempfile::tempdir_in(&indexes_path)?;
+            if copy_documents {
+                src.copy_to_path(tmp.path())?;
+            } else {
+                let primary_key = src.meta()?.primary_key;
+                let settings = src.settings()?;
+                let index = Index::open(
+                    tmp.path(),
+                    index_size,
+                    file_store.clone(),
+                    uuid,
+                    update_handler.clone(),
+                )?;
+                index.init_settings(primary_key, &settings)?;
+                index.prepare_for_closing().wait();
+            }
+
+            std::fs::rename(tmp.into_path(), &path)?;
+            let index = Index::open(path, index_size, file_store, uuid, update_handler)?;
+            if copy_documents {
+                // The copied environment holds the metadata of the source index as well.
+                index.reset_clone_metadata()?;
+            }
+            Ok(index)
+        })
+        .await??;
+
+        let mut lock = self.index_store.write().await;
+        if lock.contains(&uuid) {
+            return Err(IndexResolverError::IndexAlreadyExists);
+        }
+        lock.insert(uuid, index.clone());
+
+        Ok(index)
+    }
+
     async fn get(&self, uuid: Uuid) -> Result<Option<Index>> {
-        let guard = self.index_store.read().await;
-        match guard.get(&uuid) {
-            Some(index) => Ok(Some(index.clone())),
-            None => {
-                // drop the guard here so we can perform the write after without deadlocking;
-                drop(guard);
-                let path = self.path.join(format!("{}", uuid));
-                if !path.exists() {
-                    return Ok(None);
+        let mut lock = loop {
+            let lock = self.index_store.read().await;
+            if let Some(index) = lock.get(&uuid) {
+                return Ok(Some(index));
+            }
+            // A growing index is reopened by `grow`.
+            match lock.growing(&uuid) {
+                Some(notify) => {
+                    let reopened = notify.notified();
+                    drop(lock);
+                    reopened.await;
+                    continue;
                 }
+                None => drop(lock),
+            }
 
-                let index_size = self.index_size;
-                let file_store = self.update_file_store.clone();
-                let update_handler = self.update_handler.clone();
-                let index = spawn_blocking(move || {
-                    Index::open(path, index_size, file_store, uuid, update_handler)
-                })
-                .await??;
-                self.index_store.write().await.insert(uuid, index.clone());
-                Ok(Some(index))
+            let path = self.path.join(format!("{}", uuid));
+            if !path.exists() {
+                return Ok(None);
             }
-        }
+
+            // Keep the lock while opening the index, so that it is not opened twice.
+            let lock = self.index_store.write().await;
+            if let Some(index) = lock.get(&uuid) {
+                return Ok(Some(index));
+            }
+            if lock.growing(&uuid).is_none() {
+                break lock;
+            }
+        };
+
+        let closing = lock.take_closing_event(&uuid);
+        let index_size = self.index_size;
+        let file_store = self.update_file_store.clone();
+        let update_handler = self.update_handler.clone();
+        let path = self.path.join(format!("{}", uuid));
+        let index = spawn_blocking(move || {
+            if let Some(closing) = closing {
+                closing.wait();
+            }
+            Index::open(path, index_size, file_store, uuid, update_handler)
+        })
+        .await??;
+        lock.insert(uuid, index.clone());
+        Ok(Some(index))
+    }
+
+    async fn get_opened(&self, uuid: Uuid) -> Result<Option<Index>> {
+        Ok(self.index_store.read().await.peek(&uuid))
     }
 
     async fn delete(&self, uuid: Uuid) -> Result<Option<Index>> {
@@ -120,4 +385,62 @@ impl IndexStore for MapIndexStore {
         let index = self.index_store.write().await.remove(&uuid);
         Ok(index)
     }
+
+    async fn exists(&self, uuid: Uuid) -> Result<bool> {
+        let path = self.path.join(format!("{}", uuid));
+        Ok(fs::metadata(path).await.is_ok())
+    }
+
+    async fn grow(&self, uuid: Uuid) -> Result<Index> {
+        let path = self.path.join(format!("{}", uuid));
+
+        // The index must be closed before it is reopened with a bigger map.
+        let closing = loop {
+            let mut lock = self.index_store.write().await;
+            match lock.growing(&uuid) {
+                Some(notify) => {
+                    let reopened = notify.notified();
+                    drop(lock);
+                    reopened.await;
+                }
+                None => break lock.start_growing(uuid),
+            }
+        };
+
+        // The lock is released while waiting for the index to be closed, since it is only closed
+        // once every other user of the index is done with it.
+        let index_size = self.index_size;
+        let file_store = self.update_file_store.clone();
+        let update_handler = self.update_handler.clone();
+        let result = spawn_blocking(move || -> Result<Index> {
+            if let Some(closing) = closing {
+                closing.wait();
+            }
+
+            let map_size = Index::persisted_map_size(&path)?.unwrap_or(index_size) * 2;
+            info!("growing the map of index {} to {} bytes", uuid, map_size);
+            Index::persist_map_size(&path, map_size)?;
+            let index = Index::open(path, index_size, file_store, uuid, update_handler)?;
+            Ok(index)
+        })
+        .await;
+
+        let mut lock = self.index_store.write().await;
+        let reopened = lock.end_growing(&uuid);
+        let result = match result {
+            Ok(Ok(index)) => {
+                lock.insert(uuid, index.clone());
+                Ok(index)
+            }
+            Ok(Err(e)) => Err(e),
+            Err(e) => Err(e.into()),
+        };
+        drop(lock);
+
+        if let Some(reopened) = reopened {
+            reopened.notify_waiters();
+        }
+
+        result
+    }
 }
@@ -2,6 +2,7 @@ pub mod error;
 mod index_store;
 pub mod uuid_store;
 
+use std::collections::HashSet;
 use std::path::Path;
 
 use error::{IndexResolverError, Result};
@@ -16,13 +17,24 @@ use crate::{
 
 pub type HardStateIndexResolver = IndexResolver<HeedUuidStore, MapIndexStore>;
 
+/// The map sizes of the indexes are multiples of this size, which is a multiple of the page size
+/// of every supported platform.
+const MAP_SIZE_UNIT: usize = 1024 * 1024; // 1MiB
+
 pub fn create_index_resolver(
     path: impl AsRef<Path>,
     index_size: usize,
+    max_open_indexes: Option<usize>,
     indexer_opts: &IndexerOpts,
 ) -> anyhow::Result<HardStateIndexResolver> {
     let uuid_store = HeedUuidStore::new(&path)?;
-    let index_store = MapIndexStore::new(&path, index_size, indexer_opts)?;
+    let index_store = MapIndexStore::new(&path, index_size, max_open_indexes, indexer_opts)?;
+
+    // The documents stored before the nested fields were flattened are migrated before the
+    // indexes can be used.
+    let uuids = uuid_store.list()?.into_iter().map(|(_, uuid)| uuid);
+    index_store.flatten_stored_documents(uuids)?;
+
     Ok(IndexResolver::new(uuid_store, index_store))
 }
 
@@ -65,57 +77,116 @@ where
         }
     }
 
-    pub async fn dump(&self, path: impl AsRef<Path>) -> Result<Vec<Index>> {
-        let uuids = self.index_uuid_store.dump(path.as_ref().to_owned()).await?;
-        let mut indexes = Vec::new();
-        for uuid in uuids {
-            indexes.push(self.get_index_by_uuid(uuid).await?);
-        }
-
-        Ok(indexes)
+    /// Dumps the uuid store, and returns the uuids of the indexes to dump.
+    pub async fn dump(&self, path: impl AsRef<Path>) -> Result<HashSet<Uuid>> {
+        self.index_uuid_store.dump(path.as_ref().to_owned()).await
     }
 
     pub async fn get_uuids_size(&self) -> Result<u64> {
         Ok(self.index_uuid_store.get_size().await?)
     }
 
-    pub async fn snapshot(&self, path: impl AsRef<Path>) -> Result<Vec<Index>> {
-        let uuids = self
-            .index_uuid_store
+    /// Snapshots the uuid store, and returns the uuids of the indexes to snapshot.
+    pub async fn snapshot(&self, path: impl AsRef<Path>) -> Result<HashSet<Uuid>> {
+        self.index_uuid_store
             .snapshot(path.as_ref().to_owned())
-            .await?;
-        let mut indexes = Vec::new();
-        for uuid in uuids {
-            indexes.push(self.get_index_by_uuid(uuid).await?);
-        }
-
-        Ok(indexes)
+            .await
     }
 
-    pub async fn create_index(&self, uid: String, primary_key: Option<String>) -> Result<Index> {
+    /// Creates the index `uid`. Its map size is rounded up to a multiple of 1 MiB if it is given,
+    /// the default map size is used otherwise.
+    pub async fn create_index(
+        &self,
+        uid: String,
+        primary_key: Option<String>,
+        map_size: Option<usize>,
+    ) -> Result<Index> {
         if !is_index_uid_valid(&uid) {
             return Err(IndexResolverError::BadlyFormatted(uid));
         }
+        let map_size = match map_size {
+            Some(size) if size < MAP_SIZE_UNIT => {
+                return Err(IndexResolverError::InvalidMapSize(size))
+            }
+            Some(size) => Some((size + MAP_SIZE_UNIT - 1) / MAP_SIZE_UNIT * MAP_SIZE_UNIT),
+            None => None,
+        };
         let uuid = Uuid::new_v4();
-        let index = self.index_store.create(uuid, primary_key).await?;
+        let index = self.index_store.create(uuid, primary_key, map_size).await?;
         self.index_uuid_store.insert(uid, uuid).await?;
         Ok(index)
     }
 
-    pub async fn list(&self) -> Result<Vec<(String, Index)>> {
-        let uuids = self.index_uuid_store.list().await?;
-        let mut indexes = Vec::new();
+    /// Creates the index `new_uid` with the primary key and the settings of the index `uid`, and
+    /// its documents if `copy_documents` is set.
+    pub async fn clone_index(
+        &self,
+        uid: String,
+        new_uid: String,
+        copy_documents: bool,
+    ) -> Result<Index> {
+        if !is_index_uid_valid(&new_uid) {
+            return Err(IndexResolverError::BadlyFormatted(new_uid));
+        }
+        // Fail early rather than after copying the documents.
+        if self.get_uuid(new_uid.clone()).await.is_ok() {
+            return Err(IndexResolverError::IndexAlreadyExists);
+        }
+
+        let src = self.get_index(uid).await?;
+        let uuid = Uuid::new_v4();
+        let index = self
+            .index_store
+            .create_from(uuid, src, copy_documents)
+            .await?;
+        if let Err(e) = self.index_uuid_store.insert(new_uid, uuid).await {
+            let _ = self.index_store.delete(uuid).await;
+            return Err(e);
+        }
+
+        Ok(index)
+    }
+
+    /// Lists the `limit` indexes, or all of them, following the first `offset` indexes whose uid
+    /// starts with `uid_prefix`, sorted by uid. Only the listed indexes are opened. Also returns
+    /// the number of indexes matching the prefix.
+    pub async fn list_page(
+        &self,
+        uid_prefix: Option<String>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<(usize, Vec<(String, Index)>)> {
+        let mut uuids = self.index_uuid_store.list().await?;
+        if let Some(prefix) = uid_prefix {
+            uuids.retain(|(name, _)| name.starts_with(&prefix));
+        }
+
+        // The entries of unexisting indexes are removed before counting the indexes, so that the
+        // total matches the listed indexes.
+        let mut existing = Vec::with_capacity(uuids.len());
         for (name, uuid) in uuids {
-            match self.index_store.get(uuid).await? {
-                Some(index) => indexes.push((name, index)),
-                None => {
-                    // we found an unexisting index, we remove it from the uuid store
-                    let _ = self.index_uuid_store.delete(name).await;
-                }
+            if self.index_store.exists(uuid).await? {
+                existing.push((name, uuid));
+            } else {
+                let _ = self.index_uuid_store.delete(name).await;
             }
         }
+        let total = existing.len();
+
+        let mut indexes = Vec::new();
+        let limit = limit.unwrap_or(usize::MAX);
+        for (name, uuid) in existing.into_iter().skip(offset).take(limit) {
+            if let Some(index) = self.index_store.get(uuid).await? {
+                indexes.push((name, index));
+            }
+        }
+
+        Ok((total, indexes))
+    }
 
-        Ok(indexes)
+    /// Lists the uid and uuid of every index, without opening them.
+    pub async fn list_uuids(&self) -> Result<Vec<(String, Uuid)>> {
+        self.index_uuid_store.list().await
     }
 
     pub async fn delete_index(&self, uid: String) -> Result<Uuid> {
@@ -128,6 +199,64 @@ where
         }
     }
 
+    /// Swaps the indexes of each pair of uids, all at once. An index can only be part of a
+    /// single pair.
+    pub async fn swap_indexes(&self, pairs: Vec<(String, String)>) -> Result<()> {
+        let mut uids = HashSet::new();
+        for (lhs, rhs) in &pairs {
+            for uid in [lhs, rhs] {
+                if !uids.insert(uid) {
+                    return Err(IndexResolverError::DuplicateSwappedIndex(uid.clone()));
+                }
+            }
+        }
+
+        self.index_uuid_store.swap(pairs).await
+    }
+
+    /// Changes the uid of the index `uid` to `new_uid`, which must be valid and not taken.
+    pub async fn rename_index(&self, uid: String, new_uid: String) -> Result<()> {
+        if !is_index_uid_valid(&new_uid) {
+            return Err(IndexResolverError::BadlyFormatted(new_uid));
+        }
+
+        self.index_uuid_store.rename(uid, new_uid).await
+    }
+
+    /// Returns the uid of the index `alias` points to.
+    pub async fn get_alias(&self, alias: String) -> Result<String> {
+        match self.index_uuid_store.get_alias(alias.clone()).await? {
+            Some(uid) => Ok(uid),
+            None => Err(IndexResolverError::UnexistingAlias(alias)),
+        }
+    }
+
+    pub async fn list_aliases(&self) -> Result<Vec<(String, String)>> {
+        self.index_uuid_store.list_aliases().await
+    }
+
+    /// Makes `alias` point to the index `uid`, creating the alias if it doesn't exist.
+    pub async fn put_alias(&self, alias: String, uid: String) -> Result<()> {
+        if !is_index_uid_valid(&alias) {
+            return Err(IndexResolverError::BadlyFormattedAlias(alias));
+        }
+
+        self.index_uuid_store.put_alias(alias, uid).await
+    }
+
+    pub async fn delete_alias(&self, alias: String) -> Result<String> {
+        match self.index_uuid_store.delete_alias(alias.clone()).await? {
+            Some(uid) => Ok(uid),
+            None => Err(IndexResolverError::UnexistingAlias(alias)),
+        }
+    }
+
+    /// Doubles the map size of the index `uuid`. The index must not be used while it grows, since
+    /// it is reopened.
+    pub async fn grow_index(&self, uuid: Uuid) -> Result<Index> {
+        self.index_store.grow(uuid).await
+    }
+
     pub async fn get_index_by_uuid(&self, uuid: Uuid) -> Result<Index> {
         // TODO: Handle this error better.
         self.index_store
@@ -137,7 +266,19 @@ where
     }
 
     pub async fn get_index(&self, uid: String) -> Result<Index> {
-        match self.index_uuid_store.get_uuid(uid).await? {
+        let resolved = self.index_uuid_store.get_uuid(uid).await?;
+        self.get_resolved_index(resolved).await
+    }
+
+    /// Returns the index `uid`, or the index it is an alias of. Only the operations reading from
+    /// an index go through the aliases.
+    pub async fn get_read_index(&self, uid: String) -> Result<Index> {
+        let resolved = self.index_uuid_store.resolve_read_uuid(uid).await?;
+        self.get_resolved_index(resolved).await
+    }
+
+    async fn get_resolved_index(&self, resolved: (String, Option<Uuid>)) -> Result<Index> {
+        match resolved {
             (name, Some(uuid)) => {
                 match self.index_store.get(uuid).await? {
                     Some(index) => Ok(index),
@@ -153,6 +294,11 @@ where
         }
     }
 
+    /// Returns the index `uuid` if it is opened, without opening it.
+    pub async fn get_opened_index(&self, uuid: Uuid) -> Result<Option<Index>> {
+        self.index_store.get_opened(uuid).await
+    }
+
     pub async fn get_uuid(&self, uid: String) -> Result<Uuid> {
         match self.index_uuid_store.get_uuid(uid).await? {
             (_, Some(uuid)) => Ok(uuid),
@@ -19,16 +19,32 @@ struct DumpEntry {
     uid: String,
 }
 
+#[derive(Serialize, Deserialize)]
+struct AliasDumpEntry {
+    alias: String,
+    uid: String,
+}
+
 const UUIDS_DB_PATH: &str = "index_uuids";
+const ALIASES_DUMP_FILE: &str = "aliases.jsonl";
 
 #[async_trait::async_trait]
 pub trait UuidStore: Sized {
     // Create a new entry for `name`. Return an error if `err` and the entry already exists, return
     // the uuid otherwise.
     async fn get_uuid(&self, uid: String) -> Result<(String, Option<Uuid>)>;
+    /// Returns the uuid of the index `uid`, or of the index it is an alias of. Aliases are only
+    /// resolved to read from an index.
+    async fn resolve_read_uuid(&self, uid: String) -> Result<(String, Option<Uuid>)>;
     async fn delete(&self, uid: String) -> Result<Option<Uuid>>;
     async fn list(&self) -> Result<Vec<(String, Uuid)>>;
     async fn insert(&self, name: String, uuid: Uuid) -> Result<()>;
+    async fn swap(&self, pairs: Vec<(String, String)>) -> Result<()>;
+    async fn rename(&self, uid: String, new_uid: String) -> Result<()>;
+    async fn get_alias(&self, alias: String) -> Result<Option<String>>;
+    async fn list_aliases(&self) -> Result<Vec<(String, String)>>;
+    async fn put_alias(&self, alias: String, uid: String) -> Result<()>;
+    async fn delete_alias(&self, alias: String) -> Result<Option<String>>;
     async fn snapshot(&self, path: PathBuf) -> Result<HashSet<Uuid>>;
     async fn get_size(&self) -> Result<u64>;
     async fn dump(&self, path: PathBuf) -> Result<HashSet<Uuid>>;
@@ -38,6 +54,8 @@ pub trait UuidStore: Sized {
 pub struct HeedUuidStore {
     env: Env,
     db: Database<Str, ByteSlice>,
+    /// Maps each alias to the uid of the index it points to.
+    aliases: Database<Str, Str>,
 }
 
 impl HeedUuidStore {
@@ -46,10 +64,11 @@ impl HeedUuidStore {
         create_dir_all(&path)?;
         let mut options = EnvOpenOptions::new();
         options.map_size(UUID_STORE_SIZE); // 1GB
-        options.max_dbs(1);
+        options.max_dbs(2);
         let env = options.open(path)?;
         let db = env.create_database(Some("uuids"))?;
-        Ok(Self { env, db })
+        let aliases = env.create_database(Some("aliases"))?;
+        Ok(Self { env, db, aliases })
     }
 
     pub fn get_uuid(&self, name: &str) -> Result<Option<Uuid>> {
@@ -65,6 +84,21 @@ impl HeedUuidStore {
         }
     }
 
+    /// Returns the uuid of the index named `name`, or of the index it is an alias of.
+    pub fn resolve_read_uuid(&self, name: &str) -> Result<Option<Uuid>> {
+        let env = self.env.clone();
+        let db = self.db;
+        let txn = env.read_txn()?;
+        let uid = self.aliases.get(&txn, name)?.unwrap_or(name);
+        match db.get(&txn, uid)? {
+            Some(uuid) => {
+                let uuid = Uuid::from_slice(uuid)?;
+                Ok(Some(uuid))
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn delete(&self, uid: String) -> Result<Option<Uuid>> {
         let env = self.env.clone();
         let db = self.db;
@@ -73,6 +107,7 @@ impl HeedUuidStore {
             Some(uuid) => {
                 let uuid = Uuid::from_slice(uuid)?;
                 db.delete(&mut txn, &uid)?;
+                self.repoint_aliases(&mut txn, &uid, None)?;
                 txn.commit()?;
                 Ok(Some(uuid))
             }
@@ -98,7 +133,7 @@ impl HeedUuidStore {
         let db = self.db;
         let mut txn = env.write_txn()?;
 
-        if db.get(&txn, &name)?.is_some() {
+        if db.get(&txn, &name)?.is_some() || self.aliases.get(&txn, &name)?.is_some() {
             return Err(IndexResolverError::IndexAlreadyExists);
         }
 
@@ -107,6 +142,126 @@ impl HeedUuidStore {
         Ok(())
     }
 
+    /// Swaps the uuids of each pair of indexes, all at once.
+    pub fn swap(&self, pairs: Vec<(String, String)>) -> Result<()> {
+        let env = self.env.clone();
+        let db = self.db;
+        let mut txn = env.write_txn()?;
+
+        for (lhs, rhs) in pairs {
+            let lhs_uuid = match db.get(&txn, &lhs)? {
+                Some(uuid) => Uuid::from_slice(uuid)?,
+                None => return Err(IndexResolverError::UnexistingIndex(lhs)),
+            };
+            let rhs_uuid = match db.get(&txn, &rhs)? {
+                Some(uuid) => Uuid::from_slice(uuid)?,
+                None => return Err(IndexResolverError::UnexistingIndex(rhs)),
+            };
+
+            db.put(&mut txn, &lhs, rhs_uuid.as_bytes())?;
+            db.put(&mut txn, &rhs, lhs_uuid.as_bytes())?;
+        }
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Moves the uuid of `uid` to `new_uid`, which must not exist yet. The aliases of `uid` are
+    /// moved too.
+    pub fn rename(&self, uid: String, new_uid: String) -> Result<()> {
+        let env = self.env.clone();
+        let db = self.db;
+        let mut txn = env.write_txn()?;
+
+        if db.get(&txn, &new_uid)?.is_some() || self.aliases.get(&txn, &new_uid)?.is_some() {
+            return Err(IndexResolverError::IndexAlreadyExists);
+        }
+
+        let uuid = match db.get(&txn, &uid)? {
+            Some(uuid) => Uuid::from_slice(uuid)?,
+            None => return Err(IndexResolverError::UnexistingIndex(uid)),
+        };
+
+        db.delete(&mut txn, &uid)?;
+        db.put(&mut txn, &new_uid, uuid.as_bytes())?;
+        self.repoint_aliases(&mut txn, &uid, Some(&new_uid))?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Makes the aliases of `uid` point to `new_uid`, or removes them if it is `None`.
+    fn repoint_aliases(
+        &self,
+        txn: &mut heed::RwTxn,
+        uid: &str,
+        new_uid: Option<&str>,
+    ) -> Result<()> {
+        let mut aliases = Vec::new();
+        for entry in self.aliases.iter(txn)? {
+            let (alias, target) = entry?;
+            if target == uid {
+                aliases.push(alias.to_owned());
+            }
+        }
+
+        for alias in aliases {
+            match new_uid {
+                Some(new_uid) => self.aliases.put(txn, &alias, new_uid)?,
+                None => {
+                    self.aliases.delete(txn, &alias)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_alias(&self, alias: &str) -> Result<Option<String>> {
+        let txn = self.env.read_txn()?;
+        let uid = self.aliases.get(&txn, alias)?.map(ToOwned::to_owned);
+        Ok(uid)
+    }
+
+    pub fn list_aliases(&self) -> Result<Vec<(String, String)>> {
+        let txn = self.env.read_txn()?;
+        let mut entries = Vec::new();
+        for entry in self.aliases.iter(&txn)? {
+            let (alias, uid) = entry?;
+            entries.push((alias.to_owned(), uid.to_owned()));
+        }
+        Ok(entries)
+    }
+
+    /// Makes `alias` point to the index `uid`, whether the alias already exists or not. An alias
+    /// can't have the uid of an index.
+    pub fn put_alias(&self, alias: String, uid: String) -> Result<()> {
+        let mut txn = self.env.write_txn()?;
+
+        if self.db.get(&txn, &alias)?.is_some() {
+            return Err(IndexResolverError::BadlyFormattedAlias(alias));
+        }
+        if self.db.get(&txn, &uid)?.is_none() {
+            return Err(IndexResolverError::UnexistingIndex(uid));
+        }
+
+        self.aliases.put(&mut txn, &alias, &uid)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Removes `alias`, and returns the uid of the index it pointed to.
+    pub fn delete_alias(&self, alias: String) -> Result<Option<String>> {
+        let mut txn = self.env.write_txn()?;
+        match self.aliases.get(&txn, &alias)?.map(ToOwned::to_owned) {
+            Some(uid) => {
+                self.aliases.delete(&mut txn, &alias)?;
+                txn.commit()?;
+                Ok(Some(uid))
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn snapshot(&self, mut path: PathBuf) -> Result<HashSet<Uuid>> {
         let env = self.env.clone();
         let db = self.db;
@@ -153,6 +308,17 @@ impl HeedUuidStore {
             uuids.insert(uuid);
         }
 
+        let mut aliases_file = File::create(dump_path.join(ALIASES_DUMP_FILE))?;
+        for entry in self.aliases.iter(&txn)? {
+            let (alias, uid) = entry?;
+            let entry = AliasDumpEntry {
+                alias: alias.to_string(),
+                uid: uid.to_string(),
+            };
+            serde_json::to_writer(&mut aliases_file, &entry)?;
+            aliases_file.write_all(b"\n")?;
+        }
+
         Ok(uuids)
     }
 
@@ -180,6 +346,17 @@ impl HeedUuidStore {
 
             line.clear();
         }
+
+        // Dumps made before the aliases were introduced don't have an aliases file.
+        let src_aliases = src.as_ref().join(UUIDS_DB_PATH).join(ALIASES_DUMP_FILE);
+        if src_aliases.exists() {
+            let aliases = BufReader::new(File::open(&src_aliases)?);
+            for line in aliases.lines() {
+                let AliasDumpEntry { alias, uid } = serde_json::from_str(&line?)?;
+                db.aliases.put(&mut txn, &alias, &uid)?;
+            }
+        }
+
         txn.commit()?;
 
         db.env.prepare_for_closing().wait();
@@ -195,6 +372,12 @@ impl UuidStore for HeedUuidStore {
         tokio::task::spawn_blocking(move || this.get_uuid(&name).map(|res| (name, res))).await?
     }
 
+    async fn resolve_read_uuid(&self, name: String) -> Result<(String, Option<Uuid>)> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.resolve_read_uuid(&name).map(|res| (name, res)))
+            .await?
+    }
+
     async fn delete(&self, uid: String) -> Result<Option<Uuid>> {
         let this = self.clone();
         tokio::task::spawn_blocking(move || this.delete(uid)).await?
@@ -210,6 +393,36 @@ impl UuidStore for HeedUuidStore {
         tokio::task::spawn_blocking(move || this.insert(name, uuid)).await?
     }
 
+    async fn swap(&self, pairs: Vec<(String, String)>) -> Result<()> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.swap(pairs)).await?
+    }
+
+    async fn rename(&self, uid: String, new_uid: String) -> Result<()> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.rename(uid, new_uid)).await?
+    }
+
+    async fn get_alias(&self, alias: String) -> Result<Option<String>> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.get_alias(&alias)).await?
+    }
+
+    async fn list_aliases(&self) -> Result<Vec<(String, String)>> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.list_aliases()).await?
+    }
+
+    async fn put_alias(&self, alias: String, uid: String) -> Result<()> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.put_alias(alias, uid)).await?
+    }
+
+    async fn delete_alias(&self, alias: String) -> Result<Option<String>> {
+        let this = self.clone();
+        tokio::task::spawn_blocking(move || this.delete_alias(alias)).await?
+    }
+
     async fn snapshot(&self, path: PathBuf) -> Result<HashSet<Uuid>> {
         let this = self.clone();
         tokio::task::spawn_blocking(move || this.snapshot(path)).await?
@@ -1,18 +1,23 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use actix_web::error::PayloadError;
+use async_stream::stream;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use futures::Stream;
 use log::info;
-use milli::update::IndexDocumentsMethod;
+use milli::update::{IndexDocumentsMethod, Setting};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::task::spawn_blocking;
-use tokio::time::sleep;
+use tokio::time::{sleep, timeout};
 use uuid::Uuid;
 
 use dump_actor::DumpActorHandle;
@@ -21,25 +26,35 @@ use snapshot::load_snapshot;
 
 use crate::index::error::Result as IndexResult;
 use crate::index::{
-    Checked, Document, IndexMeta, IndexStats, SearchQuery, SearchResult, Settings, Unchecked,
+    Checked, Document, IndexMeta, IndexMetaUpdate, IndexStats, IndexUserMetadata, SearchQuery,
+    SearchResult, Settings, Unchecked,
 };
 use crate::index_controller::index_resolver::create_index_resolver;
 use crate::index_controller::snapshot::SnapshotService;
 use crate::options::IndexerOpts;
-use error::Result;
+use error::{IndexControllerError, Result};
 
 use self::dump_actor::load_dump;
 use self::index_resolver::error::IndexResolverError;
 use self::index_resolver::HardStateIndexResolver;
+use self::uid_locks::UidLocks;
+use self::updates::error::UpdateLoopError;
 use self::updates::status::UpdateStatus;
-use self::updates::UpdateMsg;
+use self::updates::{RetentionPolicy, UpdateEvent, UpdateMsg, UpdateOptions};
+use self::validation::{validate_documents, DocumentsValidation};
 
 mod dump_actor;
 pub mod error;
 mod index_resolver;
 mod snapshot;
+mod uid_locks;
 pub mod update_file_store;
 pub mod updates;
+pub mod validation;
+
+/// Maximum duration an index swap waits for the pending updates of the swapped indexes, while
+/// holding back the operations on these indexes.
+const SWAP_TIMEOUT: Duration = Duration::from_secs(5 * 60);
 
 pub type Payload = Box<
     dyn Stream<Item = std::result::Result<Bytes, PayloadError>> + Send + Sync + 'static + Unpin,
@@ -51,21 +66,70 @@ pub struct IndexMetadata {
     #[serde(skip)]
     pub uuid: Uuid,
     pub uid: String,
-    name: String,
     #[serde(flatten)]
     pub meta: IndexMeta,
 }
 
+impl IndexMetadata {
+    /// The display name of an index defaults to its uid.
+    pub fn new(uuid: Uuid, uid: String, mut meta: IndexMeta) -> Self {
+        if meta.name.is_none() {
+            meta.name = Some(uid.clone());
+        }
+        Self { uuid, uid, meta }
+    }
+}
+
+/// An update seen across all the indexes.
+#[derive(Debug)]
+pub struct Task {
+    /// The global id of the update.
+    pub uid: u64,
+    pub index_uid: String,
+    pub update: UpdateStatus,
+}
+
+/// An event of the stream of the status changes of the tasks.
+#[derive(Debug)]
+pub enum TaskEvent {
+    /// The status of a task changed.
+    Task(Task),
+    /// This number of events were missed because the stream was read too slowly. The tasks must
+    /// be listed again to know their current status.
+    Lagged(u64),
+}
+
+pub type TaskEvents = Pin<Box<dyn Stream<Item = TaskEvent> + Send>>;
+
+/// A stable name pointing to an index.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexAlias {
+    pub alias: String,
+    pub index_uid: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct IndexSettings {
     pub uid: Option<String>,
     pub primary_key: Option<String>,
+    /// Replaces the user metadata of the index when it is set.
+    pub metadata: Option<IndexUserMetadata>,
+    /// The new display name of the index, it is removed when the setting is reset.
+    pub name: Setting<String>,
 }
 
 #[derive(Clone)]
 pub struct IndexController {
     index_resolver: Arc<HardStateIndexResolver>,
     update_sender: updates::UpdateSender,
+    update_events: broadcast::Sender<UpdateEvent>,
+    /// Locks on the index uids, read while registering an update, or deleting or renaming an
+    /// index, and written while swapping indexes so that they are not modified while swapped.
+    uid_locks: Arc<UidLocks>,
+    /// Incremented each time the uid of an index changes, so that the uids cached by the task
+    /// streams are refreshed.
+    uids_version: Arc<AtomicU64>,
     dump_handle: dump_actor::DumpActorHandleImpl,
 }
 
@@ -107,12 +171,26 @@ pub enum Update {
         primary_key: Option<String>,
         method: IndexDocumentsMethod,
         format: DocumentAdditionFormat,
+        /// The field holding the version of the documents. When set, the documents with a lower
+        /// version than the stored ones are skipped.
+        version_field: Option<String>,
+        /// Skip the invalid documents instead of failing the whole update.
+        lenient: bool,
+    },
+    DocumentEdition {
+        function: String,
+        filter: Option<Value>,
+    },
+    /// Changes the primary key of the index, even if it already has documents.
+    PrimaryKeyChange {
+        primary_key: String,
     },
 }
 
 #[derive(Default, Debug)]
 pub struct IndexControllerBuilder {
     max_index_size: Option<usize>,
+    max_open_indexes: Option<usize>,
     max_update_store_size: Option<usize>,
     snapshot_dir: Option<PathBuf>,
     import_snapshot: Option<PathBuf>,
@@ -122,6 +200,9 @@ pub struct IndexControllerBuilder {
     schedule_snapshot: bool,
     dump_src: Option<PathBuf>,
     dump_dst: Option<PathBuf>,
+    update_retention: RetentionPolicy,
+    update_workers: usize,
+    notify_all_updates: bool,
 }
 
 impl IndexControllerBuilder {
@@ -137,6 +218,10 @@ impl IndexControllerBuilder {
             .max_index_size
             .ok_or_else(|| anyhow::anyhow!("Missing update database size"))?;
 
+        if self.max_open_indexes == Some(0) {
+            anyhow::bail!("The maximum number of opened indexes must be at least 1");
+        }
+
         if let Some(ref path) = self.import_snapshot {
             info!("Loading from snapshot {:?}", path);
             load_snapshot(
@@ -160,12 +245,19 @@ impl IndexControllerBuilder {
         let index_resolver = Arc::new(create_index_resolver(
             &db_path,
             index_size,
+            self.max_open_indexes,
             &indexer_options,
         )?);
 
         #[allow(unreachable_code)]
-        let update_sender =
-            updates::create_update_handler(index_resolver.clone(), &db_path, update_store_size)?;
+        let (update_sender, update_events) = updates::create_update_handler(
+            index_resolver.clone(),
+            &db_path,
+            update_store_size,
+            self.update_retention,
+            self.update_workers,
+            self.notify_all_updates,
+        )?;
 
         let dump_path = self
             .dump_dst
@@ -199,6 +291,9 @@ impl IndexControllerBuilder {
         Ok(IndexController {
             index_resolver,
             update_sender,
+            update_events,
+            uid_locks: Arc::new(UidLocks::default()),
+            uids_version: Arc::new(AtomicU64::new(0)),
             dump_handle,
         })
     }
@@ -214,6 +309,33 @@ impl IndexControllerBuilder {
         self
     }
 
+    /// Set the index controller builder's maximum number of opened indexes. The least recently
+    /// used indexes are closed when it is exceeded. There is no limit by default.
+    pub fn set_max_open_indexes(&mut self, max_open_indexes: usize) -> &mut Self {
+        self.max_open_indexes.replace(max_open_indexes);
+        self
+    }
+
+    /// Set the index controller builder's update history retention policy.
+    pub fn set_update_retention(&mut self, update_retention: RetentionPolicy) -> &mut Self {
+        self.update_retention = update_retention;
+        self
+    }
+
+    /// Set the index controller builder's number of update workers, which process the updates of
+    /// different indexes concurrently.
+    pub fn set_update_workers(&mut self, update_workers: usize) -> &mut Self {
+        self.update_workers = update_workers;
+        self
+    }
+
+    /// Makes every finished update go through the webhook outbox, and not only the updates
+    /// registered with a webhook. It must be set when a default webhook is notified.
+    pub fn set_notify_all_updates(&mut self, notify_all_updates: bool) -> &mut Self {
+        self.notify_all_updates = notify_all_updates;
+        self
+    }
+
     /// Set the index controller builder's snapshot path.
     pub fn set_snapshot_dir(&mut self, snapshot_dir: PathBuf) -> &mut Self {
         self.snapshot_dir.replace(snapshot_dir);
@@ -277,16 +399,30 @@ impl IndexController {
         update: Update,
         create_index: bool,
     ) -> Result<UpdateStatus> {
+        self.register_update_with_options(uid, update, create_index, UpdateOptions::default())
+            .await
+    }
+
+    /// Registers an update with the given options, such as its priority.
+    pub async fn register_update_with_options(
+        &self,
+        uid: String,
+        update: Update,
+        create_index: bool,
+        options: UpdateOptions,
+    ) -> Result<UpdateStatus> {
+        let _uid_lock = self.uid_locks.read([uid.as_str()]).await;
         match self.index_resolver.get_uuid(uid).await {
             Ok(uuid) => {
-                let update_result = UpdateMsg::update(&self.update_sender, uuid, update).await?;
+                let update_result =
+                    UpdateMsg::update(&self.update_sender, uuid, update, options).await?;
                 Ok(update_result)
             }
             Err(IndexResolverError::UnexistingIndex(name)) => {
                 if create_index {
-                    let index = self.index_resolver.create_index(name, None).await?;
+                    let index = self.index_resolver.create_index(name, None, None).await?;
                     let update_result =
-                        UpdateMsg::update(&self.update_sender, index.uuid, update).await?;
+                        UpdateMsg::update(&self.update_sender, index.uuid, update, options).await?;
                     Ok(update_result)
                 } else {
                     Err(IndexResolverError::UnexistingIndex(name).into())
@@ -296,33 +432,280 @@ impl IndexController {
         }
     }
 
+    /// Checks a document addition payload without registering any update. The primary key of the
+    /// index takes precedence over the provided one, as it does when the update is processed.
+    /// The validation doesn't open the index: if it is not opened already, its primary key is
+    /// not known, and the provided or inferred one is used instead.
+    pub async fn validate_documents(
+        &self,
+        uid: String,
+        payload: Payload,
+        format: DocumentAdditionFormat,
+        primary_key: Option<String>,
+    ) -> Result<DocumentsValidation> {
+        let index = match self.index_resolver.get_uuid(uid).await {
+            Ok(uuid) => self.index_resolver.get_opened_index(uuid).await?,
+            Err(IndexResolverError::UnexistingIndex(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let index_primary_key = match index {
+            Some(index) => spawn_blocking(move || index.meta()).await??.primary_key,
+            None => None,
+        };
+        let primary_key = index_primary_key.or(primary_key);
+
+        let validation =
+            spawn_blocking(move || validate_documents(payload, format, primary_key)).await??;
+        Ok(validation)
+    }
+
     pub async fn update_status(&self, uid: String, id: u64) -> Result<UpdateStatus> {
         let uuid = self.index_resolver.get_uuid(uid).await?;
         let result = UpdateMsg::get_update(&self.update_sender, uuid, id).await?;
         Ok(result)
     }
 
+    /// Waits at most `duration` for the update to be processed, failed or aborted, and returns
+    /// its status at the end of the wait.
+    pub async fn wait_update(
+        &self,
+        uid: String,
+        id: u64,
+        duration: Duration,
+    ) -> Result<UpdateStatus> {
+        let uuid = self.index_resolver.get_uuid(uid).await?;
+        // Subscribe before reading the status, so that the end of the update can't be missed.
+        let events = self.update_events.subscribe();
+        let status = UpdateMsg::get_update(&self.update_sender, uuid, id).await?;
+        if status.finished_at().is_some() {
+            return Ok(status);
+        }
+
+        if let Ok(result) = timeout(duration, self.wait_update_end(events, uuid, id)).await {
+            result?;
+        }
+
+        let result = UpdateMsg::get_update(&self.update_sender, uuid, id).await?;
+        Ok(result)
+    }
+
+    async fn wait_update_end(
+        &self,
+        mut events: broadcast::Receiver<UpdateEvent>,
+        uuid: Uuid,
+        id: u64,
+    ) -> Result<()> {
+        loop {
+            match events.recv().await {
+                Ok(UpdateEvent { index_uuid, status })
+                    if index_uuid == uuid
+                        && status.id() == id
+                        && status.finished_at().is_some() =>
+                {
+                    return Ok(())
+                }
+                Ok(_) => (),
+                // Some events were missed, the update may have finished in the meantime.
+                Err(RecvError::Lagged(_)) => {
+                    let status = UpdateMsg::get_update(&self.update_sender, uuid, id).await?;
+                    if status.finished_at().is_some() {
+                        return Ok(());
+                    }
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
     pub async fn all_update_status(&self, uid: String) -> Result<Vec<UpdateStatus>> {
         let uuid = self.index_resolver.get_uuid(uid).await?;
         let result = UpdateMsg::list_updates(&self.update_sender, uuid).await?;
         Ok(result)
     }
 
-    pub async fn list_indexes(&self) -> Result<Vec<IndexMetadata>> {
-        let indexes = self.index_resolver.list().await?;
+    /// Lists the enqueued tasks of every index matching `filter`, from the most recent to the
+    /// oldest. Only the queue of the enqueued tasks is read.
+    pub async fn list_enqueued_tasks(&self, filter: impl Fn(&Task) -> bool) -> Result<Vec<Task>> {
+        let updates = UpdateMsg::list_pending_updates(&self.update_sender).await?;
+        let tasks = self.resolve_tasks(updates).await?;
+        Ok(tasks.into_iter().filter(|task| filter(task)).collect())
+    }
+
+    /// Lists at most `limit` tasks matching `filter`, from the task `from`, or the most recent
+    /// one, to the oldest. Also returns the uid of the next matching task, if there is one. The
+    /// tasks are read page by page, so that only the tasks up to the next one are read.
+    pub async fn list_tasks(
+        &self,
+        from: Option<u64>,
+        limit: usize,
+        filter: impl Fn(&Task) -> bool,
+    ) -> Result<(Vec<Task>, Option<u64>)> {
+        let page_size = limit.saturating_add(1);
+        let mut tasks = Vec::new();
+        let mut from = from;
+
+        loop {
+            let updates =
+                UpdateMsg::list_updates_page(&self.update_sender, from, page_size).await?;
+            let exhausted = updates.len() < page_size;
+            from = updates
+                .last()
+                .and_then(|(_, update)| update.global_id())
+                .and_then(|global_id| global_id.checked_sub(1));
+
+            for task in self.resolve_tasks(updates).await? {
+                if !filter(&task) {
+                    continue;
+                }
+                if tasks.len() == limit {
+                    return Ok((tasks, Some(task.uid)));
+                }
+                tasks.push(task);
+            }
+
+            if exhausted || from.is_none() {
+                return Ok((tasks, None));
+            }
+        }
+    }
+
+    /// Aborts the enqueued tasks with the given uids, and returns the aborted tasks. The tasks
+    /// that are not enqueued anymore are ignored.
+    pub async fn cancel_tasks(&self, uids: HashSet<u64>) -> Result<Vec<Task>> {
+        let aborted = UpdateMsg::abort(&self.update_sender, uids).await?;
+        self.resolve_tasks(aborted).await
+    }
+
+    /// Deletes the finished tasks that are not retained by `policy`, for the given indexes or for
+    /// every index if `index_uids` is `None`. Returns the number of deleted tasks.
+    pub async fn prune_tasks(
+        &self,
+        policy: RetentionPolicy,
+        index_uids: Option<Vec<String>>,
+    ) -> Result<usize> {
+        let uuids = match index_uids {
+            Some(index_uids) => {
+                let mut uuids = HashSet::new();
+                for uid in index_uids {
+                    uuids.insert(self.index_resolver.get_uuid(uid).await?);
+                }
+                Some(uuids)
+            }
+            None => None,
+        };
+
+        let deleted = UpdateMsg::prune(&self.update_sender, policy, uuids).await?;
+        Ok(deleted)
+    }
+
+    /// Returns the finished tasks whose webhook is not notified yet, from the oldest. They stay
+    /// in the outbox until they are acknowledged, even across restarts.
+    pub async fn webhook_outbox(&self) -> Result<Vec<Task>> {
+        let updates = UpdateMsg::webhook_outbox(&self.update_sender).await?;
+        self.resolve_tasks(updates).await
+    }
+
+    /// Removes the task `uid` from the webhook outbox, once its webhook has been notified.
+    pub async fn acknowledge_webhook(&self, uid: u64) -> Result<()> {
+        UpdateMsg::acknowledge_webhook(&self.update_sender, uid).await?;
+        Ok(())
+    }
+
+    /// Returns a stream of the status changes of the tasks of every index that happen from now
+    /// on.
+    pub fn task_events(&self) -> TaskEvents {
+        self.task_stream(None)
+    }
+
+    /// Returns a stream of the status changes of the tasks of the index `index_uid` that happen
+    /// from now on.
+    pub async fn index_task_events(&self, index_uid: String) -> Result<TaskEvents> {
+        let index_uuid = self.index_resolver.get_uuid(index_uid).await?;
+        Ok(self.task_stream(Some(index_uuid)))
+    }
+
+    fn task_stream(&self, index_uuid: Option<Uuid>) -> TaskEvents {
+        let mut events = self.update_events.subscribe();
+        let index_resolver = self.index_resolver.clone();
+        let uids_version = self.uids_version.clone();
+
+        Box::pin(stream! {
+            let mut uids = HashMap::new();
+            let mut version = uids_version.load(Ordering::SeqCst);
+            loop {
+                let UpdateEvent { index_uuid: uuid, status } = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        yield TaskEvent::Lagged(missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if index_uuid.map_or(false, |index_uuid| index_uuid != uuid) {
+                    continue;
+                }
+                // The index may have been created, renamed or swapped since the uids were listed.
+                let current_version = uids_version.load(Ordering::SeqCst);
+                if !uids.contains_key(&uuid) || current_version != version {
+                    version = current_version;
+                    if let Ok(list) = index_resolver.list_uuids().await {
+                        uids = list.into_iter().map(|(uid, uuid)| (uuid, uid)).collect();
+                    }
+                }
+                if let (Some(uid), Some(index_uid)) = (status.global_id(), uids.get(&uuid)) {
+                    yield TaskEvent::Task(Task {
+                        uid,
+                        index_uid: index_uid.clone(),
+                        update: (*status).clone(),
+                    });
+                }
+            }
+        })
+    }
+
+    async fn resolve_tasks(&self, updates: Vec<(Uuid, UpdateStatus)>) -> Result<Vec<Task>> {
+        let uids: HashMap<Uuid, String> = self
+            .index_resolver
+            .list_uuids()
+            .await?
+            .into_iter()
+            .map(|(uid, uuid)| (uuid, uid))
+            .collect();
+
+        let tasks = updates
+            .into_iter()
+            .filter_map(|(uuid, update)| {
+                Some(Task {
+                    uid: update.global_id()?,
+                    index_uid: uids.get(&uuid)?.clone(),
+                    update,
+                })
+            })
+            .collect();
+
+        Ok(tasks)
+    }
+
+    /// Lists a page of the indexes whose uid starts with `uid_prefix`, sorted by uid, along with
+    /// the number of indexes matching the prefix. Every index following `offset` is listed if
+    /// there is no `limit`.
+    pub async fn list_indexes(
+        &self,
+        uid_prefix: Option<String>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<(usize, Vec<IndexMetadata>)> {
+        let (total, indexes) = self
+            .index_resolver
+            .list_page(uid_prefix, offset, limit)
+            .await?;
         let mut ret = Vec::new();
         for (uid, index) in indexes {
             let meta = index.meta()?;
-            let meta = IndexMetadata {
-                uuid: index.uuid,
-                name: uid.clone(),
-                uid,
-                meta,
-            };
-            ret.push(meta);
+            ret.push(IndexMetadata::new(index.uuid, uid, meta));
         }
 
-        Ok(ret)
+        Ok((total, ret))
     }
 
     pub async fn settings(&self, uid: String) -> Result<Settings<Checked>> {
@@ -338,7 +721,7 @@ impl IndexController {
         limit: usize,
         attributes_to_retrieve: Option<Vec<String>>,
     ) -> Result<Vec<Document>> {
-        let index = self.index_resolver.get_index(uid).await?;
+        let index = self.index_resolver.get_read_index(uid).await?;
         let documents =
             spawn_blocking(move || index.retrieve_documents(offset, limit, attributes_to_retrieve))
                 .await??;
@@ -351,35 +734,61 @@ impl IndexController {
         doc_id: String,
         attributes_to_retrieve: Option<Vec<String>>,
     ) -> Result<Document> {
-        let index = self.index_resolver.get_index(uid).await?;
+        let index = self.index_resolver.get_read_index(uid).await?;
         let document =
             spawn_blocking(move || index.retrieve_document(doc_id, attributes_to_retrieve))
                 .await??;
         Ok(document)
     }
 
+    /// Updates the primary key and the metadata of the index, and renames it if a new uid is
+    /// given. Either all the changes are applied, or none is.
     pub async fn update_index(
         &self,
         uid: String,
-        mut index_settings: IndexSettings,
+        index_settings: IndexSettings,
     ) -> Result<IndexMetadata> {
-        index_settings.uid.take();
+        let _uid_locks = self
+            .uid_locks
+            .read(std::iter::once(uid.as_str()).chain(index_settings.uid.as_deref()))
+            .await;
+        let new_uid = index_settings.uid.filter(|new_uid| *new_uid != uid);
 
         let index = self.index_resolver.get_index(uid.clone()).await?;
         let uuid = index.uuid;
-        let meta =
-            spawn_blocking(move || index.update_primary_key(index_settings.primary_key)).await??;
-        let meta = IndexMetadata {
-            uuid,
-            name: uid.clone(),
-            uid,
-            meta,
+
+        // The index is renamed first, so that nothing is updated if it can't be renamed, and
+        // renamed back if its metadata can't be updated.
+        if let Some(ref new_uid) = new_uid {
+            self.index_resolver
+                .rename_index(uid.clone(), new_uid.clone())
+                .await?;
+            self.uids_version.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let update = IndexMetaUpdate {
+            primary_key: index_settings.primary_key,
+            metadata: index_settings.metadata,
+            name: index_settings.name,
+        };
+        let meta = match spawn_blocking(move || index.update_meta(update)).await? {
+            Ok(meta) => meta,
+            Err(e) => {
+                if let Some(new_uid) = new_uid {
+                    self.index_resolver.rename_index(new_uid, uid).await?;
+                    self.uids_version.fetch_add(1, Ordering::SeqCst);
+                }
+                return Err(e.into());
+            }
         };
+
+        let uid = new_uid.unwrap_or(uid);
+        let meta = IndexMetadata::new(uuid, uid, meta);
         Ok(meta)
     }
 
     pub async fn search(&self, uid: String, query: SearchQuery) -> Result<SearchResult> {
-        let index = self.index_resolver.get_index(uid.clone()).await?;
+        let index = self.index_resolver.get_read_index(uid.clone()).await?;
         let result = spawn_blocking(move || index.perform_search(query)).await??;
         Ok(result)
     }
@@ -388,22 +797,17 @@ impl IndexController {
         let index = self.index_resolver.get_index(uid.clone()).await?;
         let uuid = index.uuid;
         let meta = spawn_blocking(move || index.meta()).await??;
-        let meta = IndexMetadata {
-            uuid,
-            name: uid.clone(),
-            uid,
-            meta,
-        };
+        let meta = IndexMetadata::new(uuid, uid, meta);
         Ok(meta)
     }
 
     pub async fn get_index_stats(&self, uid: String) -> Result<IndexStats> {
         let update_infos = UpdateMsg::get_info(&self.update_sender).await?;
-        let index = self.index_resolver.get_index(uid).await?;
+        let index = self.index_resolver.get_read_index(uid).await?;
         let uuid = index.uuid;
         let mut stats = spawn_blocking(move || index.stats()).await??;
         // Check if the currently indexing update is from our index.
-        stats.is_indexing = Some(Some(uuid) == update_infos.processing);
+        stats.is_indexing = Some(update_infos.processing.contains(&uuid));
         Ok(stats)
     }
 
@@ -413,8 +817,14 @@ impl IndexController {
         let mut last_update: Option<DateTime<_>> = None;
         let mut indexes = BTreeMap::new();
 
-        for (index_uid, index) in self.index_resolver.list().await? {
-            let uuid = index.uuid;
+        // The indexes are opened one at a time, so that they can be closed if too many indexes are
+        // opened.
+        for (index_uid, uuid) in self.index_resolver.list_uuids().await? {
+            let index = match self.index_resolver.get_index_by_uuid(uuid).await {
+                Ok(index) => index,
+                Err(IndexResolverError::UnexistingIndex(_)) => continue,
+                Err(e) => return Err(e.into()),
+            };
             let (mut stats, meta) = spawn_blocking::<_, IndexResult<_>>(move || {
                 let stats = index.stats()?;
                 let meta = index.meta()?;
@@ -429,7 +839,7 @@ impl IndexController {
             });
 
             // Check if the currently indexing update is from our index.
-            stats.is_indexing = Some(Some(uuid) == update_infos.processing);
+            stats.is_indexing = Some(update_infos.processing.contains(&uuid));
 
             indexes.insert(index_uid, stats);
         }
@@ -449,32 +859,157 @@ impl IndexController {
         Ok(self.dump_handle.dump_info(uid).await?)
     }
 
+    /// Creates the index `uid`, with a map of `map_size` bytes if it is given. The map grows
+    /// automatically when it is full.
     pub async fn create_index(
         &self,
         uid: String,
         primary_key: Option<String>,
+        map_size: Option<usize>,
+        name: Option<String>,
+        metadata: Option<IndexUserMetadata>,
     ) -> Result<IndexMetadata> {
         let index = self
             .index_resolver
-            .create_index(uid.clone(), primary_key)
+            .create_index(uid.clone(), None, map_size)
             .await?;
-        let meta = spawn_blocking(move || -> IndexResult<_> {
-            let meta = index.meta()?;
-            let meta = IndexMetadata {
-                uuid: index.uuid,
-                uid: uid.clone(),
-                name: uid,
-                meta,
-            };
-            Ok(meta)
-        })
-        .await??;
+        let uuid = index.uuid;
+        let update = IndexMetaUpdate {
+            primary_key,
+            metadata,
+            name: name.map_or(Setting::NotSet, Setting::Set),
+        };
+        match spawn_blocking(move || index.update_meta(update)).await? {
+            Ok(meta) => Ok(IndexMetadata::new(uuid, uid, meta)),
+            Err(e) => {
+                // The index is removed rather than left without the requested metadata.
+                let _ = self.index_resolver.delete_index(uid).await;
+                Err(e.into())
+            }
+        }
+    }
 
+    pub async fn get_alias(&self, alias: String) -> Result<IndexAlias> {
+        let index_uid = self.index_resolver.get_alias(alias.clone()).await?;
+        Ok(IndexAlias { alias, index_uid })
+    }
+
+    pub async fn list_aliases(&self) -> Result<Vec<IndexAlias>> {
+        let aliases = self
+            .index_resolver
+            .list_aliases()
+            .await?
+            .into_iter()
+            .map(|(alias, index_uid)| IndexAlias { alias, index_uid })
+            .collect();
+        Ok(aliases)
+    }
+
+    /// Makes `alias` point to the index `index_uid`. The indexes can then be read and searched
+    /// through their aliases.
+    pub async fn put_alias(&self, alias: String, index_uid: String) -> Result<IndexAlias> {
+        self.index_resolver
+            .put_alias(alias.clone(), index_uid.clone())
+            .await?;
+        Ok(IndexAlias { alias, index_uid })
+    }
+
+    pub async fn delete_alias(&self, alias: String) -> Result<()> {
+        self.index_resolver.delete_alias(alias).await?;
+        Ok(())
+    }
+
+    /// Creates the index `new_uid` from the primary key and the settings of the index `uid`. Its
+    /// documents are copied too if `copy_documents` is set.
+    pub async fn clone_index(
+        &self,
+        uid: String,
+        new_uid: String,
+        copy_documents: bool,
+    ) -> Result<IndexMetadata> {
+        let _uid_locks = self.uid_locks.read([uid.as_str(), new_uid.as_str()]).await;
+        let index = self
+            .index_resolver
+            .clone_index(uid, new_uid.clone(), copy_documents)
+            .await?;
+        let uuid = index.uuid;
+        let meta = spawn_blocking(move || index.meta()).await??;
+        let meta = IndexMetadata::new(uuid, new_uid, meta);
         Ok(meta)
     }
 
+    /// Swaps the indexes of each pair of uids, all at once. The updates of the swapped indexes
+    /// registered before are processed first, and the updates registered after are applied to
+    /// the swapped indexes. Fails without swapping anything if the updates registered before are
+    /// not processed within `SWAP_TIMEOUT`.
+    pub async fn swap_indexes(&self, pairs: Vec<(String, String)>) -> Result<()> {
+        // Only the operations on the swapped indexes wait for the swap.
+        let _uid_locks = self
+            .uid_locks
+            .write(
+                pairs
+                    .iter()
+                    .flat_map(|(lhs, rhs)| [lhs.as_str(), rhs.as_str()]),
+            )
+            .await;
+
+        let mut uuids = Vec::new();
+        for (lhs, rhs) in &pairs {
+            uuids.push(self.index_resolver.get_uuid(lhs.clone()).await?);
+            uuids.push(self.index_resolver.get_uuid(rhs.clone()).await?);
+        }
+
+        timeout(SWAP_TIMEOUT, self.wait_pending_updates(&uuids))
+            .await
+            .map_err(|_| IndexControllerError::SwapTimeout(SWAP_TIMEOUT))??;
+        self.index_resolver.swap_indexes(pairs).await?;
+        self.uids_version.fetch_add(1, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Waits until the enqueued and processing updates of the indexes `uuids` are finished.
+    async fn wait_pending_updates(&self, uuids: &[Uuid]) -> Result<()> {
+        // Subscribe before listing the updates, so that the end of an update can't be missed.
+        let mut events = self.update_events.subscribe();
+        let mut pending = self.pending_updates(uuids).await?;
+
+        while !pending.is_empty() {
+            match events.recv().await {
+                Ok(UpdateEvent { index_uuid, status }) if status.finished_at().is_some() => {
+                    pending.remove(&(index_uuid, status.id()));
+                }
+                Ok(_) => (),
+                // Some events were missed, the updates may have finished in the meantime.
+                Err(RecvError::Lagged(_)) => pending = self.pending_updates(uuids).await?,
+                // The update store was shut down, the updates will never finish.
+                Err(RecvError::Closed) => return Err(UpdateLoopError::FatalUpdateStoreError.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the enqueued and processing updates of the indexes `uuids`.
+    async fn pending_updates(&self, uuids: &[Uuid]) -> Result<HashSet<(Uuid, u64)>> {
+        let mut pending = HashSet::new();
+        for uuid in uuids {
+            let updates = UpdateMsg::list_updates(&self.update_sender, *uuid).await?;
+            pending.extend(
+                updates
+                    .iter()
+                    .filter(|update| update.finished_at().is_none())
+                    .map(|update| (*uuid, update.id())),
+            );
+        }
+
+        Ok(pending)
+    }
+
     pub async fn delete_index(&self, uid: String) -> Result<()> {
+        let _uid_lock = self.uid_locks.read([uid.as_str()]).await;
         let uuid = self.index_resolver.delete_index(uid).await?;
+        self.uids_version.fetch_add(1, Ordering::SeqCst);
 
         let update_sender = self.update_sender.clone();
         tokio::spawn(async move {
@@ -60,16 +60,16 @@ impl SnapshotService {
         let temp_snapshot_dir = spawn_blocking(tempfile::tempdir).await??;
         let temp_snapshot_path = temp_snapshot_dir.path().to_owned();
 
-        let indexes = self
+        let uuids = self
             .index_resolver
             .snapshot(temp_snapshot_path.clone())
             .await?;
 
-        if indexes.is_empty() {
+        if uuids.is_empty() {
             return Ok(());
         }
 
-        UpdateMsg::snapshot(&self.update_sender, temp_snapshot_path.clone(), indexes).await?;
+        UpdateMsg::snapshot(&self.update_sender, temp_snapshot_path.clone(), uuids).await?;
 
         let snapshot_path = self
             .snapshot_path
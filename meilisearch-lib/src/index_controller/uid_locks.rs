@@ -0,0 +1,62 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Number of locks the index uids are spread over.
+const STRIPES: usize = 64;
+
+/// Locks on the index uids, read while an index is modified and written while it is swapped, so
+/// that swapping indexes only blocks the operations on the swapped uids. The uids are spread over
+/// a fixed number of locks, two uids may then share the same lock.
+pub struct UidLocks {
+    stripes: Vec<RwLock<()>>,
+}
+
+impl Default for UidLocks {
+    fn default() -> Self {
+        Self {
+            stripes: (0..STRIPES).map(|_| RwLock::new(())).collect(),
+        }
+    }
+}
+
+impl UidLocks {
+    /// Returns the positions of the locks of `uids`, sorted so that they are always acquired in
+    /// the same order.
+    fn stripes<'a>(&self, uids: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+        let mut stripes: Vec<_> = uids
+            .into_iter()
+            .map(|uid| {
+                let mut hasher = DefaultHasher::new();
+                uid.hash(&mut hasher);
+                hasher.finish() as usize % self.stripes.len()
+            })
+            .collect();
+        stripes.sort_unstable();
+        stripes.dedup();
+        stripes
+    }
+
+    pub async fn read<'a>(
+        &self,
+        uids: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<RwLockReadGuard<'_, ()>> {
+        let mut guards = Vec::new();
+        for stripe in self.stripes(uids) {
+            guards.push(self.stripes[stripe].read().await);
+        }
+        guards
+    }
+
+    pub async fn write<'a>(
+        &self,
+        uids: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<RwLockWriteGuard<'_, ()>> {
+        let mut guards = Vec::new();
+        for stripe in self.stripes(uids) {
+            guards.push(self.stripes[stripe].write().await);
+        }
+        guards
+    }
+}
@@ -50,7 +50,9 @@ internal_error!(
     std::io::Error,
     serde_json::Error,
     tokio::task::JoinError,
-    UpdateFileStoreError
+    UpdateFileStoreError,
+    crate::index::error::IndexError,
+    crate::index_controller::index_resolver::error::IndexResolverError
 );
 
 impl ErrorCode for UpdateLoopError {
@@ -1,24 +1,48 @@
+use std::collections::HashSet;
 use std::path::PathBuf;
 
 use tokio::sync::{mpsc, oneshot};
 use uuid::Uuid;
 
-use crate::index::Index;
-
 use super::error::Result;
-use super::{Update, UpdateStatus, UpdateStoreInfo};
+use super::{RetentionPolicy, Update, UpdateOptions, UpdateStatus, UpdateStoreInfo};
 
 #[derive(Debug)]
 pub enum UpdateMsg {
     Update {
         uuid: Uuid,
         update: Update,
+        options: UpdateOptions,
         ret: oneshot::Sender<Result<UpdateStatus>>,
     },
     ListUpdates {
         uuid: Uuid,
         ret: oneshot::Sender<Result<Vec<UpdateStatus>>>,
     },
+    ListPendingUpdates {
+        ret: oneshot::Sender<Result<Vec<(Uuid, UpdateStatus)>>>,
+    },
+    ListUpdatesPage {
+        from: Option<u64>,
+        limit: usize,
+        ret: oneshot::Sender<Result<Vec<(Uuid, UpdateStatus)>>>,
+    },
+    Abort {
+        global_ids: HashSet<u64>,
+        ret: oneshot::Sender<Result<Vec<(Uuid, UpdateStatus)>>>,
+    },
+    Prune {
+        policy: RetentionPolicy,
+        uuids: Option<HashSet<Uuid>>,
+        ret: oneshot::Sender<Result<usize>>,
+    },
+    WebhookOutbox {
+        ret: oneshot::Sender<Result<Vec<(Uuid, UpdateStatus)>>>,
+    },
+    AcknowledgeWebhook {
+        global_id: u64,
+        ret: oneshot::Sender<Result<()>>,
+    },
     GetUpdate {
         uuid: Uuid,
         ret: oneshot::Sender<Result<UpdateStatus>>,
@@ -29,12 +53,12 @@ pub enum UpdateMsg {
         ret: oneshot::Sender<Result<()>>,
     },
     Snapshot {
-        indexes: Vec<Index>,
+        uuids: HashSet<Uuid>,
         path: PathBuf,
         ret: oneshot::Sender<Result<()>>,
     },
     Dump {
-        indexes: Vec<Index>,
+        uuids: HashSet<Uuid>,
         path: PathBuf,
         ret: oneshot::Sender<Result<()>>,
     },
@@ -47,21 +71,21 @@ impl UpdateMsg {
     pub async fn snapshot(
         sender: &mpsc::Sender<Self>,
         path: PathBuf,
-        indexes: Vec<Index>,
+        uuids: HashSet<Uuid>,
     ) -> Result<()> {
         let (ret, rcv) = oneshot::channel();
-        let msg = Self::Snapshot { path, indexes, ret };
+        let msg = Self::Snapshot { path, uuids, ret };
         sender.send(msg).await?;
         rcv.await?
     }
 
     pub async fn dump(
         sender: &mpsc::Sender<Self>,
-        indexes: Vec<Index>,
+        uuids: HashSet<Uuid>,
         path: PathBuf,
     ) -> Result<()> {
         let (ret, rcv) = oneshot::channel();
-        let msg = Self::Dump { path, indexes, ret };
+        let msg = Self::Dump { path, uuids, ret };
         sender.send(msg).await?;
         rcv.await?
     }
@@ -69,9 +93,15 @@ impl UpdateMsg {
         sender: &mpsc::Sender<Self>,
         uuid: Uuid,
         update: Update,
+        options: UpdateOptions,
     ) -> Result<UpdateStatus> {
         let (ret, rcv) = oneshot::channel();
-        let msg = Self::Update { uuid, update, ret };
+        let msg = Self::Update {
+            uuid,
+            update,
+            options,
+            ret,
+        };
         sender.send(msg).await?;
         rcv.await?
     }
@@ -97,6 +127,61 @@ impl UpdateMsg {
         rcv.await?
     }
 
+    pub async fn list_pending_updates(
+        sender: &mpsc::Sender<Self>,
+    ) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let (ret, rcv) = oneshot::channel();
+        let msg = Self::ListPendingUpdates { ret };
+        sender.send(msg).await?;
+        rcv.await?
+    }
+
+    pub async fn list_updates_page(
+        sender: &mpsc::Sender<Self>,
+        from: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let (ret, rcv) = oneshot::channel();
+        let msg = Self::ListUpdatesPage { from, limit, ret };
+        sender.send(msg).await?;
+        rcv.await?
+    }
+
+    pub async fn abort(
+        sender: &mpsc::Sender<Self>,
+        global_ids: HashSet<u64>,
+    ) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let (ret, rcv) = oneshot::channel();
+        let msg = Self::Abort { global_ids, ret };
+        sender.send(msg).await?;
+        rcv.await?
+    }
+
+    pub async fn prune(
+        sender: &mpsc::Sender<Self>,
+        policy: RetentionPolicy,
+        uuids: Option<HashSet<Uuid>>,
+    ) -> Result<usize> {
+        let (ret, rcv) = oneshot::channel();
+        let msg = Self::Prune { policy, uuids, ret };
+        sender.send(msg).await?;
+        rcv.await?
+    }
+
+    pub async fn webhook_outbox(sender: &mpsc::Sender<Self>) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let (ret, rcv) = oneshot::channel();
+        let msg = Self::WebhookOutbox { ret };
+        sender.send(msg).await?;
+        rcv.await?
+    }
+
+    pub async fn acknowledge_webhook(sender: &mpsc::Sender<Self>, global_id: u64) -> Result<()> {
+        let (ret, rcv) = oneshot::channel();
+        let msg = Self::AcknowledgeWebhook { global_id, ret };
+        sender.send(msg).await?;
+        rcv.await?
+    }
+
     pub async fn get_info(sender: &mpsc::Sender<Self>) -> Result<UpdateStoreInfo> {
         let (ret, rcv) = oneshot::channel();
         let msg = Self::GetInfo { ret };
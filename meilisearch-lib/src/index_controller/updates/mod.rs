@@ -3,6 +3,7 @@ mod message;
 pub mod status;
 pub mod store;
 
+use std::collections::HashSet;
 use std::io::{self, BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
@@ -15,14 +16,15 @@ use futures::{Stream, StreamExt};
 use log::trace;
 use milli::update::IndexDocumentsMethod;
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use uuid::Uuid;
 
 use self::error::{Result, UpdateLoopError};
 pub use self::message::UpdateMsg;
+pub use self::store::{RetentionPolicy, UpdateEvent};
 use self::store::{UpdateStore, UpdateStoreInfo};
 use crate::document_formats::{read_csv, read_json, read_ndjson};
-use crate::index::{Index, Settings, Unchecked};
+use crate::index::{Settings, Unchecked};
 use crate::index_controller::update_file_store::UpdateFileStore;
 use status::UpdateStatus;
 
@@ -31,28 +33,52 @@ use super::{DocumentAdditionFormat, Update};
 
 pub type UpdateSender = mpsc::Sender<UpdateMsg>;
 
+/// The options of an update, given when it is registered.
+#[derive(Debug, Default, Clone)]
+pub struct UpdateOptions {
+    /// The updates with a higher priority are processed before the ones registered earlier,
+    /// unless they would change the result of the earlier updates of their index.
+    pub priority: u8,
+    /// The url notified when the update is finished, instead of the default one.
+    pub webhook: Option<String>,
+}
+
+/// Spawns the update loop, and returns the sender of its messages along with the sender of the
+/// status changes of the updates.
 pub fn create_update_handler(
     index_resolver: Arc<HardStateIndexResolver>,
     db_path: impl AsRef<Path>,
     update_store_size: usize,
-) -> anyhow::Result<UpdateSender> {
+    retention: RetentionPolicy,
+    workers: usize,
+    notify_all_updates: bool,
+) -> anyhow::Result<(UpdateSender, broadcast::Sender<UpdateEvent>)> {
     let path = db_path.as_ref().to_owned();
     let (sender, receiver) = mpsc::channel(100);
-    let actor = UpdateLoop::new(update_store_size, receiver, path, index_resolver)?;
-
+    let actor = UpdateLoop::new(
+        update_store_size,
+        receiver,
+        path,
+        index_resolver,
+        retention,
+        workers,
+        notify_all_updates,
+    )?;
+
+    let events = actor.store.events();
     tokio::task::spawn(actor.run());
 
-    Ok(sender)
+    Ok((sender, events))
 }
 
 /// A wrapper type to implement read on a `Stream<Result<Bytes, Error>>`.
-struct StreamReader<S> {
+pub(crate) struct StreamReader<S> {
     stream: S,
     current: Option<Bytes>,
 }
 
 impl<S> StreamReader<S> {
-    fn new(stream: S) -> Self {
+    pub(crate) fn new(stream: S) -> Self {
         Self {
             stream,
             current: None,
@@ -89,6 +115,7 @@ impl<S: Stream<Item = std::result::Result<Bytes, PayloadError>> + Unpin> io::Rea
 
 pub struct UpdateLoop {
     store: Arc<UpdateStore>,
+    index_resolver: Arc<HardStateIndexResolver>,
     inbox: Option<mpsc::Receiver<UpdateMsg>>,
     update_file_store: UpdateFileStore,
     must_exit: Arc<AtomicBool>,
@@ -100,6 +127,9 @@ impl UpdateLoop {
         inbox: mpsc::Receiver<UpdateMsg>,
         path: impl AsRef<Path>,
         index_resolver: Arc<HardStateIndexResolver>,
+        retention: RetentionPolicy,
+        workers: usize,
+        notify_all_updates: bool,
     ) -> anyhow::Result<Self> {
         let path = path.as_ref().to_owned();
         std::fs::create_dir_all(&path)?;
@@ -113,15 +143,19 @@ impl UpdateLoop {
         let store = UpdateStore::open(
             options,
             &path,
-            index_resolver,
+            index_resolver.clone(),
             must_exit.clone(),
             update_file_store.clone(),
+            retention,
+            workers,
+            notify_all_updates,
         )?;
 
         let inbox = Some(inbox);
 
         Ok(Self {
             store,
+            index_resolver,
             inbox,
             must_exit,
             update_file_store,
@@ -157,39 +191,69 @@ impl UpdateLoop {
         stream
             .for_each_concurrent(Some(10), |msg| async {
                 match msg {
-                    Update { uuid, update, ret } => {
-                        let _ = ret.send(self.handle_update(uuid, update).await);
+                    Update {
+                        uuid,
+                        update,
+                        options,
+                        ret,
+                    } => {
+                        let _ = ret.send(self.handle_update(uuid, update, options).await);
                     }
                     ListUpdates { uuid, ret } => {
                         let _ = ret.send(self.handle_list_updates(uuid).await);
                     }
+                    ListPendingUpdates { ret } => {
+                        let _ = ret.send(self.handle_list_pending_updates().await);
+                    }
+                    ListUpdatesPage { from, limit, ret } => {
+                        let _ = ret.send(self.handle_list_updates_page(from, limit).await);
+                    }
+                    Abort { global_ids, ret } => {
+                        let _ = ret.send(self.handle_abort(global_ids).await);
+                    }
+                    Prune { policy, uuids, ret } => {
+                        let _ = ret.send(self.handle_prune(policy, uuids).await);
+                    }
+                    WebhookOutbox { ret } => {
+                        let _ = ret.send(self.handle_webhook_outbox().await);
+                    }
+                    AcknowledgeWebhook { global_id, ret } => {
+                        let _ = ret.send(self.handle_acknowledge_webhook(global_id).await);
+                    }
                     GetUpdate { uuid, ret, id } => {
                         let _ = ret.send(self.handle_get_update(uuid, id).await);
                     }
                     DeleteIndex { uuid, ret } => {
                         let _ = ret.send(self.handle_delete(uuid).await);
                     }
-                    Snapshot { indexes, path, ret } => {
-                        let _ = ret.send(self.handle_snapshot(indexes, path).await);
+                    Snapshot { uuids, path, ret } => {
+                        let _ = ret.send(self.handle_snapshot(uuids, path).await);
                     }
                     GetInfo { ret } => {
                         let _ = ret.send(self.handle_get_info().await);
                     }
-                    Dump { indexes, path, ret } => {
-                        let _ = ret.send(self.handle_dump(indexes, path).await);
+                    Dump { uuids, path, ret } => {
+                        let _ = ret.send(self.handle_dump(uuids, path).await);
                     }
                 }
             })
             .await;
     }
 
-    async fn handle_update(&self, index_uuid: Uuid, update: Update) -> Result<UpdateStatus> {
+    async fn handle_update(
+        &self,
+        index_uuid: Uuid,
+        update: Update,
+        options: UpdateOptions,
+    ) -> Result<UpdateStatus> {
         let registration = match update {
             Update::DocumentAddition {
                 payload,
                 primary_key,
                 method,
                 format,
+                version_field,
+                lenient,
             } => {
                 let mut reader = BufReader::new(StreamReader::new(payload));
                 let (content_uuid, mut update_file) = self.update_file_store.new_update()?;
@@ -216,17 +280,26 @@ impl UpdateLoop {
                     primary_key,
                     method,
                     content_uuid,
+                    version_field,
+                    lenient,
                 }
             }
             Update::Settings(settings) => store::Update::Settings(settings),
             Update::ClearDocuments => store::Update::ClearDocuments,
             Update::DeleteDocuments(ids) => store::Update::DeleteDocuments(ids),
+            Update::DocumentEdition { function, filter } => {
+                store::Update::DocumentEdition { function, filter }
+            }
+            Update::PrimaryKeyChange { primary_key } => {
+                store::Update::PrimaryKeyChange { primary_key }
+            }
         };
 
         let store = self.store.clone();
-        let status =
-            tokio::task::spawn_blocking(move || store.register_update(index_uuid, registration))
-                .await??;
+        let status = tokio::task::spawn_blocking(move || {
+            store.register_update(index_uuid, registration, options)
+        })
+        .await??;
 
         Ok(status.into())
     }
@@ -240,6 +313,60 @@ impl UpdateLoop {
         .await?
     }
 
+    async fn handle_list_pending_updates(&self) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let update_store = self.store.clone();
+        tokio::task::spawn_blocking(move || {
+            let result = update_store.list_pending()?;
+            Ok(result)
+        })
+        .await?
+    }
+
+    async fn handle_list_updates_page(
+        &self,
+        from: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let update_store = self.store.clone();
+        tokio::task::spawn_blocking(move || update_store.list_page(from, limit)).await?
+    }
+
+    async fn handle_abort(&self, global_ids: HashSet<u64>) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let update_store = self.store.clone();
+        tokio::task::spawn_blocking(move || {
+            let aborted = update_store
+                .abort(&global_ids)?
+                .into_iter()
+                .map(|(uuid, update)| (uuid, update.into()))
+                .collect();
+            Ok(aborted)
+        })
+        .await?
+    }
+
+    async fn handle_prune(
+        &self,
+        policy: RetentionPolicy,
+        uuids: Option<HashSet<Uuid>>,
+    ) -> Result<usize> {
+        let update_store = self.store.clone();
+        tokio::task::spawn_blocking(move || {
+            let deleted = update_store.prune(&policy, uuids.as_ref())?;
+            Ok(deleted)
+        })
+        .await?
+    }
+
+    async fn handle_webhook_outbox(&self) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let update_store = self.store.clone();
+        tokio::task::spawn_blocking(move || update_store.webhook_outbox()).await?
+    }
+
+    async fn handle_acknowledge_webhook(&self, global_id: u64) -> Result<()> {
+        let update_store = self.store.clone();
+        tokio::task::spawn_blocking(move || update_store.acknowledge_webhook(global_id)).await?
+    }
+
     async fn handle_get_update(&self, uuid: Uuid, id: u64) -> Result<UpdateStatus> {
         let store = self.store.clone();
         tokio::task::spawn_blocking(move || {
@@ -259,19 +386,22 @@ impl UpdateLoop {
         Ok(())
     }
 
-    async fn handle_snapshot(&self, indexes: Vec<Index>, path: PathBuf) -> Result<()> {
+    async fn handle_snapshot(&self, uuids: HashSet<Uuid>, path: PathBuf) -> Result<()> {
         let update_store = self.store.clone();
+        let index_resolver = self.index_resolver.clone();
 
-        tokio::task::spawn_blocking(move || update_store.snapshot(indexes, path)).await??;
+        tokio::task::spawn_blocking(move || update_store.snapshot(&uuids, &index_resolver, path))
+            .await??;
 
         Ok(())
     }
 
-    async fn handle_dump(&self, indexes: Vec<Index>, path: PathBuf) -> Result<()> {
+    async fn handle_dump(&self, uuids: HashSet<Uuid>, path: PathBuf) -> Result<()> {
         let update_store = self.store.clone();
+        let index_resolver = self.index_resolver.clone();
 
         tokio::task::spawn_blocking(move || -> Result<()> {
-            update_store.dump(&indexes, path.to_path_buf())?;
+            update_store.dump(&uuids, &index_resolver, path.to_path_buf())?;
             Ok(())
         })
         .await??;
@@ -3,18 +3,49 @@ use std::{error::Error, fmt::Display};
 use chrono::{DateTime, Utc};
 
 use meilisearch_error::{Code, ErrorCode};
-use milli::update::{DocumentAdditionResult, IndexDocumentsMethod};
+use milli::update::{IndexDocumentsMethod, UpdateIndexingStep};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     index::{Settings, Unchecked},
+    index_controller::{updates::UpdateOptions, validation::InvalidDocument},
     Update,
 };
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentAdditionResult {
+    /// The number of documents that were indexed.
+    pub nb_documents: usize,
+    /// The number of documents that were skipped because a newer version of them was already
+    /// stored. Only reported when the addition is versioned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nb_skipped_documents: Option<usize>,
+    /// The number of documents that were left out of a lenient addition because they were
+    /// invalid.
+    #[serde(default)]
+    pub nb_invalid_documents: usize,
+    /// The first `MAX_REPORTED_ERRORS` documents that were left out of a lenient addition.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalid_documents: Vec<InvalidDocument>,
+}
+
+impl From<milli::update::DocumentAdditionResult> for DocumentAdditionResult {
+    fn from(other: milli::update::DocumentAdditionResult) -> Self {
+        Self {
+            nb_documents: other.nb_documents,
+            nb_skipped_documents: None,
+            nb_invalid_documents: 0,
+            invalid_documents: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UpdateResult {
     DocumentsAddition(DocumentAdditionResult),
     DocumentDeletion { deleted: u64 },
+    DocumentEdition { edited: u64 },
+    PrimaryKeyChange { documents: u64 },
     Other,
 }
 
@@ -37,16 +68,34 @@ pub enum UpdateMeta {
 #[serde(rename_all = "camelCase")]
 pub struct Enqueued {
     pub update_id: u64,
+    /// The id of the update across all the indexes, `None` for the updates that were enqueued
+    /// before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global_id: Option<u64>,
     pub meta: Update,
     pub enqueued_at: DateTime<Utc>,
+    /// The updates with a higher priority are processed first, as long as it doesn't change the
+    /// result of the previous updates of their index.
+    #[serde(default, skip_serializing_if = "is_default_priority")]
+    pub priority: u8,
+    /// The url notified when the update is finished, instead of the default one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+fn is_default_priority(priority: &u8) -> bool {
+    *priority == 0
 }
 
 impl Enqueued {
-    pub fn new(meta: Update, update_id: u64) -> Self {
+    pub fn new(meta: Update, update_id: u64, global_id: u64, options: UpdateOptions) -> Self {
         Self {
             enqueued_at: Utc::now(),
             meta,
             update_id,
+            global_id: Some(global_id),
+            priority: options.priority,
+            webhook: options.webhook,
         }
     }
 
@@ -54,6 +103,7 @@ impl Enqueued {
         Processing {
             from: self,
             started_processing_at: Utc::now(),
+            progress: None,
         }
     }
 
@@ -73,6 +123,66 @@ impl Enqueued {
     }
 }
 
+/// The latest indexing step reached by a processing update.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexingProgress {
+    pub step: String,
+    /// The position of the step, starting at 1.
+    pub current_step: usize,
+    pub total_steps: usize,
+    /// The number of documents processed so far during the step, or of databases for the last
+    /// step.
+    pub processed: usize,
+    /// The number of documents, or of databases, to process during the step, if known.
+    pub total: Option<usize>,
+}
+
+impl From<UpdateIndexingStep> for IndexingProgress {
+    fn from(step: UpdateIndexingStep) -> Self {
+        use UpdateIndexingStep::*;
+
+        let (name, current_step, processed, total) = match step {
+            TransformFromUserIntoGenericFormat { documents_seen } => (
+                "TransformFromUserIntoGenericFormat",
+                1,
+                documents_seen,
+                None,
+            ),
+            ComputeIdsAndMergeDocuments {
+                documents_seen,
+                total_documents,
+            } => (
+                "ComputeIdsAndMergeDocuments",
+                2,
+                documents_seen,
+                Some(total_documents),
+            ),
+            IndexDocuments {
+                documents_seen,
+                total_documents,
+            } => ("IndexDocuments", 3, documents_seen, Some(total_documents)),
+            MergeDataIntoFinalDatabase {
+                databases_seen,
+                total_databases,
+            } => (
+                "MergeDataIntoFinalDatabase",
+                4,
+                databases_seen,
+                Some(total_databases),
+            ),
+        };
+
+        Self {
+            step: name.to_string(),
+            current_step,
+            total_steps: 4,
+            processed,
+            total,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Processed {
@@ -98,6 +208,9 @@ pub struct Processing {
     #[serde(flatten)]
     pub from: Enqueued,
     pub started_processing_at: DateTime<Utc>,
+    /// The progress of the indexing, only known while the update is processing.
+    #[serde(default, skip_serializing)]
+    pub progress: Option<IndexingProgress>,
 }
 
 impl Processing {
@@ -147,7 +260,7 @@ impl Aborted {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Failed {
     #[serde(flatten)]
@@ -172,6 +285,11 @@ impl ErrorCode for Failed {
 }
 
 impl Failed {
+    /// Returns `true` if the update failed because the LMDB map of its index is full.
+    pub fn is_map_full(&self) -> bool {
+        matches!(self.code, Code::DatabaseSizeLimitReached)
+    }
+
     pub fn id(&self) -> u64 {
         self.from.id()
     }
@@ -181,7 +299,7 @@ impl Failed {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "status", rename_all = "camelCase")]
 pub enum UpdateStatus {
     Processing(Processing),
@@ -218,6 +336,45 @@ impl UpdateStatus {
             _ => None,
         }
     }
+
+    /// Returns the enqueued update this status originates from.
+    pub fn enqueued(&self) -> &Enqueued {
+        match self {
+            UpdateStatus::Processing(u) => &u.from,
+            UpdateStatus::Enqueued(u) => u,
+            UpdateStatus::Processed(u) => &u.from.from,
+            UpdateStatus::Aborted(u) => &u.from,
+            UpdateStatus::Failed(u) => &u.from.from,
+        }
+    }
+
+    fn enqueued_mut(&mut self) -> &mut Enqueued {
+        match self {
+            UpdateStatus::Processing(u) => &mut u.from,
+            UpdateStatus::Enqueued(u) => u,
+            UpdateStatus::Processed(u) => &mut u.from.from,
+            UpdateStatus::Aborted(u) => &mut u.from,
+            UpdateStatus::Failed(u) => &mut u.from.from,
+        }
+    }
+
+    pub fn global_id(&self) -> Option<u64> {
+        self.enqueued().global_id
+    }
+
+    pub fn set_global_id(&mut self, global_id: u64) {
+        self.enqueued_mut().global_id = Some(global_id);
+    }
+
+    /// Returns the date at which the update finished processing, if it did.
+    pub fn finished_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            UpdateStatus::Processed(u) => Some(u.processed_at),
+            UpdateStatus::Aborted(u) => Some(u.aborted_at),
+            UpdateStatus::Failed(u) => Some(u.failed_at),
+            UpdateStatus::Processing(_) | UpdateStatus::Enqueued(_) => None,
+        }
+    }
 }
 
 impl From<Enqueued> for UpdateStatus {
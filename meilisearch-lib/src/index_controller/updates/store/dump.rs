@@ -4,16 +4,16 @@ use std::io::{BufReader, Write};
 use std::path::{Path, PathBuf};
 
 use heed::{EnvOpenOptions, RoTxn};
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json::Deserializer;
 use tempfile::{NamedTempFile, TempDir};
+use tokio::runtime::Handle;
 use uuid::Uuid;
 
 use super::{Result, State, UpdateStore};
 use crate::{
-    index::Index,
     index_controller::{
+        index_resolver::HardStateIndexResolver,
         update_file_store::UpdateFileStore,
         updates::status::{Enqueued, UpdateStatus},
     },
@@ -27,21 +27,29 @@ pub struct UpdateEntry {
 }
 
 impl UpdateStore {
-    pub fn dump(&self, indexes: &[Index], path: PathBuf) -> Result<()> {
+    /// Dumps the updates and the indexes of `uuids`. The indexes are opened one at a time, so
+    /// that they can be closed once dumped if too many indexes are opened.
+    pub fn dump(
+        &self,
+        uuids: &HashSet<Uuid>,
+        index_resolver: &HardStateIndexResolver,
+        path: PathBuf,
+    ) -> Result<()> {
+        // Wait for the processing updates to be done.
+        let _processing_lock = self.processing_lock.write();
         let state_lock = self.state.write();
         state_lock.swap(State::Dumping);
 
         // txn must *always* be acquired after state lock, or it will dead lock.
         let txn = self.env.write_txn()?;
 
-        let uuids = indexes.iter().map(|i| i.uuid).collect();
+        self.dump_updates(&txn, uuids, &path)?;
 
-        self.dump_updates(&txn, &uuids, &path)?;
-
-        indexes
-            .par_iter()
-            .try_for_each(|index| index.dump(&path))
-            .unwrap();
+        let handle = Handle::current();
+        for uuid in uuids {
+            let index = handle.block_on(index_resolver.get_index_by_uuid(*uuid))?;
+            index.dump(&path)?;
+        }
 
         Ok(())
     }
@@ -136,18 +144,22 @@ impl UpdateStore {
         // create a dummy update fiel store, since it is not needed right now.
         let tmp = TempDir::new().unwrap();
         let update_file_store = UpdateFileStore::new(tmp.path()).unwrap();
-        let (store, _) = UpdateStore::new(options, &dst, update_file_store)?;
+        let store = UpdateStore::new(options, &dst, update_file_store)?;
 
         let src_update_path = src.as_ref().join("updates");
         let update_data = File::open(&src_update_path.join("data.jsonl"))?;
         let update_data = BufReader::new(update_data);
 
-        let stream = Deserializer::from_reader(update_data).into_iter::<UpdateEntry>();
+        let mut entries = Deserializer::from_reader(update_data)
+            .into_iter::<UpdateEntry>()
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        // The updates are given new global ids, in the order they were enqueued.
+        entries.sort_by_key(|entry| entry.update.global_id());
+
         let mut wtxn = store.env.write_txn()?;
 
-        for entry in stream {
-            let UpdateEntry { uuid, update } = entry?;
-            store.register_raw_updates(&mut wtxn, &update, uuid)?;
+        for UpdateEntry { uuid, update } in entries {
+            store.register_raw_updates(&mut wtxn, update, uuid)?;
         }
 
         wtxn.commit()?;
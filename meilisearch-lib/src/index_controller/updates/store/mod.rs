@@ -6,29 +6,30 @@ use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Bound,
     path::PathBuf,
     time::Duration,
 };
 
 use arc_swap::ArcSwap;
+use chrono::Utc;
 use heed::types::{ByteSlice, OwnedType, SerdeJson};
 use heed::zerocopy::U64;
 use heed::{CompactionOption, Database, Env, EnvOpenOptions};
-use log::error;
-use parking_lot::{Mutex, MutexGuard};
-use rayon::prelude::*;
+use log::{error, info};
+use milli::update::UpdateIndexingStep;
+use parking_lot::{Mutex, MutexGuard, RwLock};
+use serde_json::Value;
 use tokio::runtime::Handle;
-use tokio::sync::mpsc;
-use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, Notify};
 use tokio::time::timeout;
 use uuid::Uuid;
 
 use codec::*;
 
 use super::error::Result;
-use super::status::{Enqueued, Processing};
-use crate::index::Index;
+use super::status::{Aborted, Enqueued, IndexingProgress, Processing};
 use crate::index_controller::updates::*;
 use crate::EnvSizer;
 
@@ -43,17 +44,69 @@ pub enum Update {
         primary_key: Option<String>,
         method: IndexDocumentsMethod,
         content_uuid: Uuid,
+        #[serde(default)]
+        version_field: Option<String>,
+        #[serde(default)]
+        lenient: bool,
     },
     Settings(Settings<Unchecked>),
     ClearDocuments,
+    DocumentEdition {
+        function: String,
+        filter: Option<Value>,
+    },
+    PrimaryKeyChange {
+        primary_key: String,
+    },
+}
+
+/// Maximum number of document additions indexed together.
+const MAX_BATCHED_UPDATES: usize = 100;
+
+/// Maximum number of times the map of an index is grown to process a batch of updates that
+/// failed because the map was full.
+const MAX_MAP_GROWTHS: usize = 8;
+
+/// Time between two prunings of the update history.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum number of updates deleted in a single write transaction while pruning the update
+/// history, so that the processing of the updates is not blocked during the whole pruning.
+const PRUNE_CHUNK_SIZE: usize = 1000;
+
+/// Defines which finished updates are kept in the update history, the others are pruned.
+/// Enqueued and processing updates are never pruned.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RetentionPolicy {
+    /// The finished updates older than this are pruned.
+    pub max_age: Option<Duration>,
+    /// The maximum number of finished updates kept for each index, the oldest are pruned first.
+    pub max_count: Option<usize>,
+}
+
+impl RetentionPolicy {
+    /// Returns `true` if the policy keeps every update.
+    pub fn keeps_all(&self) -> bool {
+        self.max_age.is_none() && self.max_count.is_none()
+    }
+}
+
+/// Capacity of the update events channel. Slower subscribers miss the oldest events.
+const UPDATE_EVENTS_CAPACITY: usize = 1024;
+
+/// Published by the update store when the status of an update changes.
+#[derive(Debug, Clone)]
+pub struct UpdateEvent {
+    pub index_uuid: Uuid,
+    pub status: Arc<UpdateStatus>,
 }
 
 #[derive(Debug)]
 pub struct UpdateStoreInfo {
     /// Size of the update store in bytes.
     pub size: u64,
-    /// Uuid of the currently processing update if it exists
-    pub processing: Option<Uuid>,
+    /// Uuids of the indexes whose updates are currently processing.
+    pub processing: HashSet<Uuid>,
 }
 
 /// A data structure that allows concurrent reads AND exactly one writer.
@@ -91,14 +144,54 @@ impl StateLock {
     }
 }
 
-#[allow(clippy::large_enum_variant)]
 pub enum State {
     Idle,
-    Processing(Uuid, Processing),
+    /// The batches of updates being processed, by index.
+    Processing(HashMap<Uuid, ProcessingBatch>),
     Snapshoting,
     Dumping,
 }
 
+/// A batch of updates being processed, along with the latest step of their indexing.
+#[derive(Clone)]
+pub struct ProcessingBatch {
+    updates: Vec<Processing>,
+    progress: Arc<Mutex<Option<IndexingProgress>>>,
+}
+
+impl ProcessingBatch {
+    /// Returns the updates of the batch, with their current progress.
+    fn updates(&self) -> impl Iterator<Item = Processing> + '_ {
+        let progress = self.progress.lock().clone();
+        self.updates.iter().map(move |update| Processing {
+            progress: progress.clone(),
+            ..update.clone()
+        })
+    }
+}
+
+impl State {
+    /// Returns the batch of updates being processed for `index_uuid`, if any.
+    fn processing(&self, index_uuid: Uuid) -> Option<&ProcessingBatch> {
+        match self {
+            State::Processing(batches) => batches.get(&index_uuid),
+            _ => None,
+        }
+    }
+
+    /// Returns all the updates being processed, along with the uuid of their index.
+    fn all_processing(&self) -> impl Iterator<Item = (Uuid, Processing)> + '_ {
+        let batches = match self {
+            State::Processing(batches) => Some(batches),
+            _ => None,
+        };
+        batches
+            .into_iter()
+            .flatten()
+            .flat_map(|(uuid, batch)| batch.updates().map(move |p| (*uuid, p)))
+    }
+}
+
 #[derive(Clone)]
 pub struct UpdateStore {
     pub env: Env,
@@ -107,6 +200,9 @@ pub struct UpdateStore {
     /// | global_update_id | index_uuid | update_id |
     /// |     8-bytes      |  16-bytes  |  8-bytes  |
     pending_queue: Database<PendingKeyCodec, SerdeJson<Enqueued>>,
+    /// The priority of the pending updates that have a priority, with the same keys as the
+    /// pending queue.
+    pending_priorities: Database<PendingKeyCodec, OwnedType<u8>>,
     /// Map indexes to the next available update id. If NextIdKey::Global is queried, then the next
     /// global update id is returned
     next_update_id: Database<NextIdCodec, OwnedType<BEU64>>,
@@ -115,10 +211,24 @@ pub struct UpdateStore {
     /// |    Uuid  |   id    |
     /// | 16-bytes | 8-bytes |
     updates: Database<UpdateKeyCodec, SerdeJson<UpdateStatus>>,
+    /// The keys of the finished updates in the updates database, by global id.
+    task_ids: Database<OwnedType<BEU64>, UpdateKeyCodec>,
+    /// The finished updates whose webhook is not notified yet, by global id.
+    webhook_outbox: Database<OwnedType<BEU64>, UpdateKeyCodec>,
+    /// Whether every finished update is put in the webhook outbox, or only the ones registered
+    /// with a webhook.
+    notify_all: bool,
     /// Indicates the current state of the update store,
     state: Arc<StateLock>,
-    /// Wake up the loop when a new event occurs.
-    notification_sender: mpsc::Sender<()>,
+    /// Held while picking the next update to process, so that it can't be aborted in between.
+    pending_lock: Arc<Mutex<()>>,
+    /// Read by the workers while they process updates, and written to wait until no update is
+    /// being processed.
+    processing_lock: Arc<RwLock<()>>,
+    /// Wake up a worker when a new event occurs.
+    notification: Arc<Notify>,
+    /// Publishes the status changes of the updates.
+    events: broadcast::Sender<UpdateEvent>,
     update_file_store: UpdateFileStore,
     path: PathBuf,
 }
@@ -128,89 +238,148 @@ impl UpdateStore {
         mut options: EnvOpenOptions,
         path: impl AsRef<Path>,
         update_file_store: UpdateFileStore,
-    ) -> anyhow::Result<(Self, mpsc::Receiver<()>)> {
-        options.max_dbs(5);
+    ) -> anyhow::Result<Self> {
+        options.max_dbs(6);
 
         let update_path = path.as_ref().join("updates");
         std::fs::create_dir_all(&update_path)?;
         let env = options.open(update_path)?;
         let pending_queue = env.create_database(Some("pending-queue"))?;
+        let pending_priorities = env.create_database(Some("pending-priorities"))?;
         let next_update_id = env.create_database(Some("next-update-id"))?;
         let updates = env.create_database(Some("updates"))?;
+        let task_ids = env.create_database(Some("task-ids"))?;
+        let webhook_outbox = env.create_database(Some("webhook-outbox"))?;
 
         let state = Arc::new(StateLock::from_state(State::Idle));
+        let (events, _) = broadcast::channel(UPDATE_EVENTS_CAPACITY);
+
+        let update_store = Self {
+            env,
+            pending_queue,
+            pending_priorities,
+            next_update_id,
+            updates,
+            task_ids,
+            webhook_outbox,
+            notify_all: false,
+            state,
+            pending_lock: Arc::new(Mutex::new(())),
+            processing_lock: Arc::new(RwLock::new(())),
+            notification: Arc::new(Notify::new()),
+            events,
+            path: path.as_ref().to_owned(),
+            update_file_store,
+        };
+        update_store.index_task_ids()?;
 
-        let (notification_sender, notification_receiver) = mpsc::channel(1);
-
-        Ok((
-            Self {
-                env,
-                pending_queue,
-                next_update_id,
-                updates,
-                state,
-                notification_sender,
-                path: path.as_ref().to_owned(),
-                update_file_store,
-            },
-            notification_receiver,
-        ))
+        Ok(update_store)
+    }
+
+    /// Indexes the finished updates by global id, for the stores created before they were
+    /// indexed.
+    fn index_task_ids(&self) -> Result<()> {
+        let txn = self.env.read_txn()?;
+        if !self.task_ids.is_empty(&txn)? || self.updates.is_empty(&txn)? {
+            return Ok(());
+        }
+
+        let mut task_ids = Vec::new();
+        for entry in self.updates.iter(&txn)? {
+            let (key, update) = entry?;
+            if let Some(global_id) = update.global_id() {
+                task_ids.push((global_id, key));
+            }
+        }
+        drop(txn);
+
+        let mut txn = self.env.write_txn()?;
+        for (global_id, key) in task_ids {
+            self.task_ids.put(&mut txn, &BEU64::new(global_id), &key)?;
+        }
+        txn.commit()?;
+
+        Ok(())
     }
 
+    /// Opens the update store and spawns `workers` workers processing the updates. The updates
+    /// of an index are processed in order, by a single worker at a time. Every finished update
+    /// is put in the webhook outbox if `notify_all` is set, otherwise only the ones registered
+    /// with a webhook are.
+    #[allow(clippy::too_many_arguments)]
     pub fn open(
         options: EnvOpenOptions,
         path: impl AsRef<Path>,
         index_resolver: Arc<HardStateIndexResolver>,
         must_exit: Arc<AtomicBool>,
         update_file_store: UpdateFileStore,
+        retention: RetentionPolicy,
+        workers: usize,
+        notify_all: bool,
     ) -> anyhow::Result<Arc<Self>> {
-        let (update_store, mut notification_receiver) =
-            Self::new(options, path, update_file_store)?;
+        let mut update_store = Self::new(options, path, update_file_store)?;
+        update_store.notify_all = notify_all;
         let update_store = Arc::new(update_store);
 
-        // Send a first notification to trigger the process.
-        if let Err(TrySendError::Closed(())) = update_store.notification_sender.try_send(()) {
-            panic!("Failed to init update store");
-        }
-
         // We need a weak reference so we can take ownership on the arc later when we
         // want to close the index.
         let duration = Duration::from_secs(10 * 60); // 10 minutes
-        let update_store_weak = Arc::downgrade(&update_store);
-        tokio::task::spawn_local(async move {
-            // Block and wait for something to process with a timeout. The timeout
-            // function returns a Result and we must just unlock the loop on Result.
-            'outer: while timeout(duration, notification_receiver.recv())
-                .await
-                .map_or(true, |o| o.is_some())
-            {
-                loop {
-                    match update_store_weak.upgrade() {
-                        Some(update_store) => {
-                            let handler = index_resolver.clone();
-                            let res = tokio::task::spawn_blocking(move || {
-                                update_store.process_pending_update(handler)
-                            })
-                            .await
-                            .expect("Fatal error processing update.");
-                            match res {
-                                Ok(Some(_)) => (),
-                                Ok(None) => break,
-                                Err(e) => {
-                                    error!("Fatal error while processing an update that requires the update store to shutdown: {}", e);
-                                    must_exit.store(true, Ordering::SeqCst);
-                                    break 'outer;
+        for _ in 0..workers.max(1) {
+            let update_store_weak = Arc::downgrade(&update_store);
+            let notification = update_store.notification.clone();
+            let index_resolver = index_resolver.clone();
+            let must_exit = must_exit.clone();
+            tokio::task::spawn_local(async move {
+                'outer: loop {
+                    // Block and wait for something to process with a timeout. The timeout
+                    // function returns a Result and we must just unlock the loop on Result.
+                    let _ = timeout(duration, notification.notified()).await;
+                    loop {
+                        match update_store_weak.upgrade() {
+                            Some(update_store) => {
+                                let handler = index_resolver.clone();
+                                let res = tokio::task::spawn_blocking(move || {
+                                    update_store.process_pending_update(handler)
+                                })
+                                .await
+                                .expect("Fatal error processing update.");
+                                match res {
+                                    Ok(Some(_)) => (),
+                                    Ok(None) => break,
+                                    Err(e) => {
+                                        error!("Fatal error while processing an update that requires the update store to shutdown: {}", e);
+                                        must_exit.store(true, Ordering::SeqCst);
+                                        break 'outer;
+                                    }
                                 }
                             }
+                            // the ownership on the arc has been taken, we need to exit.
+                            None => break 'outer,
                         }
-                        // the ownership on the arc has been taken, we need to exit.
-                        None => break 'outer,
                     }
                 }
-            }
 
-            error!("Update store loop exited.");
-        });
+                error!("Update store loop exited.");
+            });
+        }
+
+        // The history is pruned on its own, so that the updates keep being processed meanwhile.
+        if !retention.keeps_all() {
+            let update_store_weak = Arc::downgrade(&update_store);
+            tokio::task::spawn_local(async move {
+                let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    match update_store_weak.upgrade() {
+                        Some(update_store) => prune_history(update_store, retention).await,
+                        None => break,
+                    }
+                }
+            });
+        }
+
+        // Send a first notification to trigger the process.
+        update_store.notification.notify_one();
 
         Ok(update_store)
     }
@@ -251,73 +420,125 @@ impl UpdateStore {
 
     /// Registers the update content in the pending store and the meta
     /// into the pending-meta store. Returns the new unique update id.
-    pub fn register_update(&self, index_uuid: Uuid, update: Update) -> heed::Result<Enqueued> {
+    pub fn register_update(
+        &self,
+        index_uuid: Uuid,
+        update: Update,
+        options: UpdateOptions,
+    ) -> heed::Result<Enqueued> {
         let mut txn = self.env.write_txn()?;
         let (global_id, update_id) = self.next_update_id(&mut txn, index_uuid)?;
-        let meta = Enqueued::new(update, update_id);
+        let meta = Enqueued::new(update, update_id, global_id, options);
 
-        self.pending_queue
-            .put(&mut txn, &(global_id, index_uuid, update_id), &meta)?;
+        let key = (global_id, index_uuid, update_id);
+        self.pending_queue.put(&mut txn, &key, &meta)?;
+        if meta.priority > 0 {
+            self.pending_priorities
+                .put(&mut txn, &key, &meta.priority)?;
+        }
 
         txn.commit()?;
 
-        if let Err(TrySendError::Closed(())) = self.notification_sender.try_send(()) {
-            panic!("Update store loop exited");
-        }
+        self.notification.notify_one();
+        self.publish(index_uuid, meta.clone().into());
 
         Ok(meta)
     }
 
     /// Push already processed update in the UpdateStore without triggering the notification
-    /// process. This is useful for the dumps.
+    /// process. This is useful for the dumps. The update is given a new global id.
     pub fn register_raw_updates(
         &self,
         wtxn: &mut heed::RwTxn,
-        update: &UpdateStatus,
+        mut update: UpdateStatus,
         index_uuid: Uuid,
     ) -> heed::Result<()> {
+        let (global_id, _update_id) = self.next_update_id(wtxn, index_uuid)?;
+        update.set_global_id(global_id);
+
         match update {
             UpdateStatus::Enqueued(enqueued) => {
-                let (global_id, _update_id) = self.next_update_id(wtxn, index_uuid)?;
-                self.pending_queue.remap_key_type::<PendingKeyCodec>().put(
-                    wtxn,
-                    &(global_id, index_uuid, enqueued.id()),
-                    enqueued,
-                )?;
-            }
-            _ => {
-                let _update_id = self.next_update_id_raw(wtxn, index_uuid)?;
-                self.updates.put(wtxn, &(index_uuid, update.id()), update)?;
+                let key = (global_id, index_uuid, enqueued.id());
+                self.pending_queue
+                    .remap_key_type::<PendingKeyCodec>()
+                    .put(wtxn, &key, &enqueued)?;
+                if enqueued.priority > 0 {
+                    self.pending_priorities
+                        .put(wtxn, &key, &enqueued.priority)?;
+                }
             }
+            _ => self.put_finished(wtxn, index_uuid, &update)?,
         }
         Ok(())
     }
 
-    /// Executes the user provided function on the next pending update (the one with the lowest id).
+    /// Executes the user provided function on the next pending update (the one with the lowest id)
+    /// of an index that is not being processed by another worker.
     /// This is asynchronous as it let the user process the update with a read-only txn and
     /// only writing the result meta to the processed-meta store *after* it has been processed.
     fn process_pending_update(
         &self,
         index_resolver: Arc<HardStateIndexResolver>,
     ) -> Result<Option<()>> {
-        // Create a read transaction to be able to retrieve the pending update in order.
+        let _processing_lock = self.processing_lock.read();
+        // Acquire the state lock, txn must *always* be acquired after state lock, or it will
+        // dead lock.
+        let state = self.state.write();
+        let pending_lock = self.pending_lock.lock();
+
+        let mut batches = match *self.state.read() {
+            State::Processing(ref batches) => batches.clone(),
+            _ => HashMap::new(),
+        };
+
+        // Create a read transaction to be able to retrieve the pending updates in order.
+        let busy: HashSet<Uuid> = batches.keys().copied().collect();
         let rtxn = self.env.read_txn()?;
-        let first_meta = self.pending_queue.first(&rtxn)?;
+        let batch = self.next_batch(&rtxn, &busy)?;
         drop(rtxn);
 
         // If there is a pending update we process and only keep
         // a reader while processing it, not a writer.
-        match first_meta {
-            Some(((global_id, index_uuid, _), pending)) => {
-                let processing = pending.processing();
-                // Acquire the state lock and set the current state to processing.
-                // txn must *always* be acquired after state lock, or it will dead lock.
-                let state = self.state.write();
-                state.swap(State::Processing(index_uuid, processing.clone()));
+        match batch {
+            Some((index_uuid, batch)) => {
+                let batch: Vec<_> = batch
+                    .into_iter()
+                    .map(|(global_id, pending)| (global_id, pending.processing()))
+                    .collect();
+                let progress = Arc::new(Mutex::new(None));
+                let processing = ProcessingBatch {
+                    updates: batch.iter().map(|(_, p)| p.clone()).collect(),
+                    progress: progress.clone(),
+                };
+                batches.insert(index_uuid, processing);
+                // Set the current state to processing, from now on the updates can't be aborted.
+                state.swap(State::Processing(batches));
+                drop(pending_lock);
+                drop(state);
+
+                // Another worker may be able to process the updates of another index.
+                self.notification.notify_one();
+
+                for (_, processing) in &batch {
+                    self.publish(index_uuid, processing.clone().into());
+                }
 
-                let result = self.perform_update(processing, index_resolver, index_uuid, global_id);
+                let result = self.perform_update(batch, index_resolver, index_uuid, progress);
 
-                state.swap(State::Idle);
+                let state = self.state.write();
+                let batches = match *self.state.read() {
+                    State::Processing(ref batches) => batches
+                        .iter()
+                        .filter(|(uuid, _)| **uuid != index_uuid)
+                        .map(|(uuid, batch)| (*uuid, batch.clone()))
+                        .collect(),
+                    _ => HashMap::new(),
+                };
+                if batches.is_empty() {
+                    state.swap(State::Idle);
+                } else {
+                    state.swap(State::Processing(batches));
+                }
 
                 result
             }
@@ -325,42 +546,292 @@ impl UpdateStore {
         }
     }
 
+    /// Returns the next updates to process, along with the uuid of their index: the pending update
+    /// with the highest priority, or the first one, of an index that is not already being
+    /// processed, followed by the next pending updates of the same index that can be batched with
+    /// it. Each update is returned with its global id.
+    fn next_batch(
+        &self,
+        txn: &heed::RoTxn,
+        busy: &HashSet<Uuid>,
+    ) -> Result<Option<(Uuid, Vec<(u64, Enqueued)>)>> {
+        let next = match self.next_prioritized_update(txn, busy)? {
+            Some(next) => Some(next),
+            None => self.next_pending_update(txn, busy)?,
+        };
+        let (key, first) = match next {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+
+        let (global_id, index_uuid, _) = key;
+        let mut batch = vec![(global_id, first)];
+        let mut pendings = self
+            .pending_queue
+            .range(txn, &(Bound::Excluded(key), Bound::Unbounded))?
+            .lazily_decode_data();
+        while batch.len() < MAX_BATCHED_UPDATES {
+            let ((global_id, uuid, _), pending) = match pendings.next().transpose()? {
+                Some(entry) => entry,
+                None => break,
+            };
+
+            // The updates of the other indexes don't need to be processed before this batch.
+            if uuid != index_uuid {
+                continue;
+            }
+
+            let pending = pending.decode()?;
+            if !can_be_batched(batch[0].1.meta(), pending.meta()) {
+                break;
+            }
+            batch.push((global_id, pending));
+        }
+
+        Ok(Some((index_uuid, batch)))
+    }
+
+    /// Returns the first pending update of an index that is not in `busy`, with its key.
+    fn next_pending_update(
+        &self,
+        txn: &heed::RoTxn,
+        busy: &HashSet<Uuid>,
+    ) -> Result<Option<((u64, Uuid, u64), Enqueued)>> {
+        for entry in self.pending_queue.iter(txn)?.lazily_decode_data() {
+            let (key, pending) = entry?;
+            if !busy.contains(&key.1) {
+                return Ok(Some((key, pending.decode()?)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the pending update with the highest priority, of an index that is not in `busy`,
+    /// that can be processed before the previous pending updates of its index. The updates with
+    /// the same priority are picked in the queue order.
+    fn next_prioritized_update(
+        &self,
+        txn: &heed::RoTxn,
+        busy: &HashSet<Uuid>,
+    ) -> Result<Option<((u64, Uuid, u64), Enqueued)>> {
+        let mut candidates = HashMap::new();
+        for entry in self.pending_priorities.iter(txn)? {
+            let (key, priority) = entry?;
+            if !busy.contains(&key.1) {
+                candidates.insert(key, priority);
+            }
+        }
+
+        let last = match candidates.keys().max() {
+            Some(last) => *last,
+            None => return Ok(None),
+        };
+        let candidate_indexes: HashSet<_> = candidates.keys().map(|(_, uuid, _)| *uuid).collect();
+
+        // The queue is read once, up to the last candidate, recording the kinds of the pending
+        // updates of each index to know which candidates can overtake them.
+        let mut kinds = HashMap::<Uuid, HashSet<UpdateKind>>::new();
+        let mut next: Option<(u8, (u64, Uuid, u64), Enqueued)> = None;
+        for entry in self
+            .pending_queue
+            .range(txn, &(..=last))?
+            .lazily_decode_data()
+        {
+            let (key, pending) = entry?;
+            if !candidate_indexes.contains(&key.1) {
+                continue;
+            }
+
+            let pending = pending.decode()?;
+            let kind = UpdateKind::of(pending.meta());
+            let previous_kinds = kinds.entry(key.1).or_default();
+            if let Some(&priority) = candidates.get(&key) {
+                let can_overtake = previous_kinds
+                    .iter()
+                    .all(|previous| previous.commutes(kind));
+                if can_overtake && next.as_ref().map_or(true, |(best, _, _)| priority > *best) {
+                    next = Some((priority, key, pending));
+                }
+            }
+            previous_kinds.insert(kind);
+        }
+
+        Ok(next.map(|(_, key, pending)| (key, pending)))
+    }
+
     fn perform_update(
         &self,
-        processing: Processing,
+        batch: Vec<(u64, Processing)>,
         index_resolver: Arc<HardStateIndexResolver>,
         index_uuid: Uuid,
-        global_id: u64,
+        progress: Arc<Mutex<Option<IndexingProgress>>>,
     ) -> Result<Option<()>> {
-        // Process the pending update using the provided user function.
+        // Process the pending updates using the provided user function.
         let handle = Handle::current();
-        let update_id = processing.id();
-        //IndexMsg::update(index_resolver, index_uuid, processing.clone()
-        let result = match handle.block_on(index_resolver.get_index_by_uuid(index_uuid)) {
-            Ok(index) => index.handle_update(processing),
-            Err(e) => Err(processing.fail(e)),
+        let (global_ids, batch): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+        let report_progress = |step: UpdateIndexingStep| *progress.lock() = Some(step.into());
+        let mut results = match handle.block_on(index_resolver.get_index_by_uuid(index_uuid)) {
+            Ok(index) => index.handle_updates(batch, &report_progress),
+            // Only the first update is failed, the others are left in the queue and will fail
+            // on their own.
+            Err(e) => {
+                let first = batch.into_iter().next().expect("empty batch");
+                vec![Err(first.fail(e))]
+            }
         };
 
-        // Once the pending update have been successfully processed
+        // The updates that failed because the map of the index is full are retried once the map
+        // has grown.
+        for _ in 0..MAX_MAP_GROWTHS {
+            let (positions, retried): (Vec<_>, Vec<_>) = results
+                .iter()
+                .enumerate()
+                .filter_map(|(i, result)| match result {
+                    Err(failed) if failed.is_map_full() => Some((i, failed.from.clone())),
+                    _ => None,
+                })
+                .unzip();
+
+            if retried.is_empty() {
+                break;
+            }
+
+            match handle.block_on(index_resolver.grow_index(index_uuid)) {
+                Ok(index) => {
+                    let retried_results = index.handle_updates(retried, &report_progress);
+                    for (i, result) in positions.into_iter().zip(retried_results) {
+                        results[i] = result;
+                    }
+                }
+                Err(e) => {
+                    error!("could not grow the map of index {}: {}", index_uuid, e);
+                    break;
+                }
+            }
+        }
+
+        // The content of the updates that still failed is not needed anymore.
+        for result in &results {
+            if let Err(failed) = result {
+                if let Update::DocumentAddition { content_uuid, .. } = failed.meta() {
+                    if failed.is_map_full() {
+                        let _ = self.update_file_store.delete(*content_uuid);
+                    }
+                }
+            }
+        }
+
+        // Once the pending updates have been successfully processed
         // we must remove the content from the pending and processing stores and
         // write the *new* meta to the processed-meta store and commit.
         let mut wtxn = self.env.write_txn()?;
-        self.pending_queue
-            .delete(&mut wtxn, &(global_id, index_uuid, update_id))?;
-
-        let result = match result {
-            Ok(res) => res.into(),
-            Err(res) => res.into(),
-        };
-
-        self.updates
-            .put(&mut wtxn, &(index_uuid, update_id), &result)?;
+        let mut statuses = Vec::with_capacity(global_ids.len());
+        for (global_id, result) in global_ids.into_iter().zip(results) {
+            let result: UpdateStatus = match result {
+                Ok(res) => res.into(),
+                Err(res) => res.into(),
+            };
+            let update_id = result.id();
+
+            let key = (global_id, index_uuid, update_id);
+            self.pending_queue.delete(&mut wtxn, &key)?;
+            self.pending_priorities.delete(&mut wtxn, &key)?;
+            self.put_finished(&mut wtxn, index_uuid, &result)?;
+            self.put_in_outbox(&mut wtxn, index_uuid, &result)?;
+            statuses.push(result);
+        }
 
         wtxn.commit()?;
 
+        for status in statuses {
+            self.publish(index_uuid, status);
+        }
+
         Ok(Some(()))
     }
 
+    /// Stores the finished update `status`, and indexes it by global id.
+    fn put_finished(
+        &self,
+        txn: &mut heed::RwTxn,
+        index_uuid: Uuid,
+        status: &UpdateStatus,
+    ) -> heed::Result<()> {
+        let key = (index_uuid, status.id());
+        self.updates.put(txn, &key, status)?;
+        if let Some(global_id) = status.global_id() {
+            self.task_ids.put(txn, &BEU64::new(global_id), &key)?;
+        }
+        Ok(())
+    }
+
+    /// Records that the webhook of the finished update `status` must be notified. The outbox is
+    /// written in the same transaction as the status, so that no notification is lost on restart.
+    fn put_in_outbox(
+        &self,
+        txn: &mut heed::RwTxn,
+        index_uuid: Uuid,
+        status: &UpdateStatus,
+    ) -> heed::Result<()> {
+        let must_notify = self.notify_all || status.enqueued().webhook.is_some();
+        if let (true, Some(global_id)) = (must_notify, status.global_id()) {
+            self.webhook_outbox
+                .put(txn, &BEU64::new(global_id), &(index_uuid, status.id()))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the finished updates whose webhook is not notified yet, along with the uuid of
+    /// their index, from the oldest. The updates deleted since they were finished are removed
+    /// from the outbox.
+    pub fn webhook_outbox(&self) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let txn = self.env.read_txn()?;
+        let mut outbox = Vec::new();
+        let mut deleted = Vec::new();
+        for entry in self.webhook_outbox.iter(&txn)? {
+            let (global_id, key) = entry?;
+            match self.updates.get(&txn, &key)? {
+                Some(update) => outbox.push((key.0, update)),
+                None => deleted.push(global_id),
+            }
+        }
+        drop(txn);
+
+        if !deleted.is_empty() {
+            let mut txn = self.env.write_txn()?;
+            for global_id in deleted {
+                self.webhook_outbox.delete(&mut txn, &global_id)?;
+            }
+            txn.commit()?;
+        }
+
+        Ok(outbox)
+    }
+
+    /// Removes the update with the given global id from the webhook outbox, once its webhook has
+    /// been notified.
+    pub fn acknowledge_webhook(&self, global_id: u64) -> Result<()> {
+        let mut txn = self.env.write_txn()?;
+        self.webhook_outbox
+            .delete(&mut txn, &BEU64::new(global_id))?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Returns the sender of the status changes of the updates, to subscribe to them.
+    pub fn events(&self) -> broadcast::Sender<UpdateEvent> {
+        self.events.clone()
+    }
+
+    fn publish(&self, index_uuid: Uuid, status: UpdateStatus) {
+        // Sending only fails when there are no subscribers.
+        let _ = self.events.send(UpdateEvent {
+            index_uuid,
+            status: Arc::new(status),
+        });
+    }
+
     /// List the updates for `index_uuid`.
     pub fn list(&self, index_uuid: Uuid) -> Result<Vec<UpdateStatus>> {
         let mut update_list = BTreeMap::<u64, UpdateStatus>::new();
@@ -385,27 +856,96 @@ impl UpdateStore {
             update_list.insert(update.id(), update);
         }
 
-        // If the currently processing update is from this index, replace the corresponding pending update with this one.
-        match *self.state.read() {
-            State::Processing(uuid, ref processing) if uuid == index_uuid => {
-                update_list.insert(processing.id(), processing.clone().into());
+        // If the currently processing updates are from this index, replace the corresponding pending updates with them.
+        if let Some(batch) = self.state.read().processing(index_uuid) {
+            for processing in batch.updates() {
+                update_list.insert(processing.id(), processing.into());
             }
-            _ => (),
         }
 
         Ok(update_list.into_iter().map(|(_, v)| v).collect())
     }
 
+    /// List the enqueued updates of all the indexes, along with the uuid of their index, from
+    /// the most recent to the oldest. Only the pending queue is read, and the updates being
+    /// processed are left out.
+    pub fn list_pending(&self) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        // The currently processing updates are still in the pending queue.
+        let processing: HashSet<u64> = self
+            .state
+            .read()
+            .all_processing()
+            .filter_map(|(_, processing)| processing.from.global_id)
+            .collect();
+
+        let txn = self.env.read_txn()?;
+        let mut update_list = Vec::new();
+
+        for entry in self.pending_queue.iter(&txn)? {
+            let ((global_id, uuid, _), pending) = entry?;
+            if !processing.contains(&global_id) {
+                let mut update = UpdateStatus::from(pending);
+                update.set_global_id(global_id);
+                update_list.push((uuid, update));
+            }
+        }
+
+        update_list.reverse();
+        Ok(update_list)
+    }
+
+    /// Returns at most `limit` updates of every index, from the one with the global id `from`,
+    /// or the most recent one, to the oldest. Only the updates of the page are read.
+    pub fn list_page(&self, from: Option<u64>, limit: usize) -> Result<Vec<(Uuid, UpdateStatus)>> {
+        let from = from.unwrap_or(u64::MAX);
+        let mut page = BTreeMap::<u64, (Uuid, UpdateStatus)>::new();
+
+        let txn = self.env.read_txn()?;
+
+        let last_pending = (from, Uuid::from_bytes([u8::MAX; 16]), u64::MAX);
+        for entry in self
+            .pending_queue
+            .rev_range(&txn, &(..=last_pending))?
+            .take(limit)
+        {
+            let ((global_id, uuid, _), pending) = entry?;
+            let mut update = UpdateStatus::from(pending);
+            update.set_global_id(global_id);
+            page.insert(global_id, (uuid, update));
+        }
+
+        let mut finished = 0;
+        for entry in self.task_ids.rev_range(&txn, &(..=BEU64::new(from)))? {
+            if finished == limit {
+                break;
+            }
+            let (global_id, key) = entry?;
+            // The index of the update may have been deleted in the meantime.
+            if let Some(update) = self.updates.get(&txn, &key)? {
+                page.insert(global_id.get(), (key.0, update));
+                finished += 1;
+            }
+        }
+
+        // The currently processing updates are still in the pending queue.
+        for (uuid, processing) in self.state.read().all_processing() {
+            if let Some(global_id) = processing.from.global_id {
+                if let Some(update) = page.get_mut(&global_id) {
+                    *update = (uuid, processing.into());
+                }
+            }
+        }
+
+        Ok(page.into_iter().rev().take(limit).map(|(_, v)| v).collect())
+    }
+
     /// Returns the update associated meta or `None` if the update doesn't exist.
     pub fn meta(&self, index_uuid: Uuid, update_id: u64) -> heed::Result<Option<UpdateStatus>> {
-        // Check if the update is the one currently processing
-        match *self.state.read() {
-            State::Processing(uuid, ref processing)
-                if uuid == index_uuid && processing.id() == update_id =>
-            {
-                return Ok(Some(processing.clone().into()));
+        // Check if the update is one of those currently processing
+        if let Some(batch) = self.state.read().processing(index_uuid) {
+            if let Some(processing) = batch.updates().find(|p| p.id() == update_id) {
+                return Ok(Some(processing.into()));
             }
-            _ => (),
         }
 
         let txn = self.env.read_txn()?;
@@ -430,6 +970,134 @@ impl UpdateStore {
         Ok(None)
     }
 
+    /// Aborts the enqueued updates whose global id is in `global_ids`, and deletes their update
+    /// files. The currently processing updates can't be aborted. Returns the aborted updates.
+    pub fn abort(&self, global_ids: &HashSet<u64>) -> Result<Vec<(Uuid, Aborted)>> {
+        let _pending_lock = self.pending_lock.lock();
+        let processing: HashSet<_> = self
+            .state
+            .read()
+            .all_processing()
+            .map(|(uuid, p)| (uuid, p.id()))
+            .collect();
+
+        let mut txn = self.env.write_txn()?;
+        let mut aborted = Vec::new();
+
+        let mut pendings = self.pending_queue.iter_mut(&mut txn)?.lazily_decode_data();
+        while let Some(entry) = pendings.next() {
+            let ((global_id, uuid, update_id), pending) = entry?;
+            if global_ids.contains(&global_id) && !processing.contains(&(uuid, update_id)) {
+                let mut pending = pending.decode()?;
+                pending.global_id = Some(global_id);
+
+                //Invariant check: we can only delete the current entry when we don't hold
+                //references to it anymore. This must be done after we have retrieved its content.
+                unsafe {
+                    pendings.del_current()?;
+                }
+
+                aborted.push((uuid, pending.abort()));
+            }
+        }
+
+        drop(pendings);
+
+        for (uuid, update) in &aborted {
+            if let Some(global_id) = update.from.global_id {
+                self.pending_priorities
+                    .delete(&mut txn, &(global_id, *uuid, update.id()))?;
+            }
+            let status = UpdateStatus::from(update.clone());
+            self.put_finished(&mut txn, *uuid, &status)?;
+            self.put_in_outbox(&mut txn, *uuid, &status)?;
+        }
+
+        txn.commit()?;
+
+        for (uuid, update) in &aborted {
+            if let Update::DocumentAddition { content_uuid, .. } = update.meta() {
+                let _ = self.update_file_store.delete(*content_uuid);
+            }
+            self.publish(*uuid, update.clone().into());
+        }
+
+        Ok(aborted)
+    }
+
+    /// Deletes the finished updates that are not retained by `policy`, for the indexes in
+    /// `index_uuids`, or for every index if it is `None`. Returns the number of deleted updates.
+    pub fn prune(
+        &self,
+        policy: &RetentionPolicy,
+        index_uuids: Option<&HashSet<Uuid>>,
+    ) -> Result<usize> {
+        if policy.keeps_all() {
+            return Ok(0);
+        }
+
+        // A max age that is out of chrono's range can't be reached.
+        let oldest = policy
+            .max_age
+            .and_then(|age| chrono::Duration::from_std(age).ok())
+            .and_then(|age| Utc::now().checked_sub_signed(age));
+
+        let txn = self.env.read_txn()?;
+
+        // Only finished updates are stored in the updates database.
+        let mut counts = HashMap::<Uuid, usize>::new();
+        for entry in self.updates.iter(&txn)?.lazily_decode_data() {
+            let ((uuid, _), _) = entry?;
+            if index_uuids.map_or(true, |uuids| uuids.contains(&uuid)) {
+                *counts.entry(uuid).or_default() += 1;
+            }
+        }
+
+        // The updates of each index are ordered by id, and so from the oldest.
+        let mut positions = HashMap::<Uuid, usize>::new();
+        let mut pruned = Vec::new();
+        for entry in self.updates.iter(&txn)?.lazily_decode_data() {
+            let ((uuid, update_id), update) = entry?;
+            let count = match counts.get(&uuid) {
+                Some(count) => *count,
+                None => continue,
+            };
+            let position = positions.entry(uuid).or_default();
+            let in_excess = policy.max_count.map_or(false, |max_count| {
+                *position < count.saturating_sub(max_count)
+            });
+            *position += 1;
+
+            let too_old = match oldest {
+                Some(oldest) if !in_excess => update
+                    .decode()?
+                    .finished_at()
+                    .map_or(false, |finished_at| finished_at < oldest),
+                _ => false,
+            };
+
+            if in_excess || too_old {
+                pruned.push((uuid, update_id));
+            }
+        }
+
+        drop(txn);
+
+        for chunk in pruned.chunks(PRUNE_CHUNK_SIZE) {
+            let mut txn = self.env.write_txn()?;
+            for key in chunk {
+                let global_id = self.updates.get(&txn, key)?.and_then(|u| u.global_id());
+                if let Some(global_id) = global_id {
+                    self.task_ids.delete(&mut txn, &BEU64::new(global_id))?;
+                }
+                self.updates.delete(&mut txn, key)?;
+            }
+            txn.commit()?;
+        }
+
+        Ok(pruned.len())
+    }
+
     /// Delete all updates for an index from the update store. If the currently processing update
     /// is for `index_uuid`, the call will block until the update is terminated.
     pub fn delete_all(&self, index_uuid: Uuid) -> Result<()> {
@@ -456,13 +1124,26 @@ impl UpdateStore {
 
         drop(pendings);
 
+        let mut priorities = self.pending_priorities.iter_mut(&mut txn)?;
+        while let Some(((_, uuid, _), _)) = priorities.next().transpose()? {
+            if uuid == index_uuid {
+                unsafe {
+                    priorities.del_current()?;
+                }
+            }
+        }
+
+        drop(priorities);
+
         let mut updates = self
             .updates
             .remap_key_type::<ByteSlice>()
             .prefix_iter_mut(&mut txn, index_uuid.as_bytes())?
             .lazily_decode_data();
 
-        while let Some(_) = updates.next() {
+        let mut global_ids = Vec::new();
+        while let Some((_, update)) = updates.next().transpose()? {
+            global_ids.extend(update.decode()?.global_id());
             unsafe {
                 updates.del_current()?;
             }
@@ -470,15 +1151,17 @@ impl UpdateStore {
 
         drop(updates);
 
+        for global_id in global_ids {
+            self.task_ids.delete(&mut txn, &BEU64::new(global_id))?;
+        }
+
         txn.commit()?;
 
-        // If the currently processing update is from our index, we wait until it is
+        // If the currently processing updates are from our index, we wait until they are
         // finished before returning. This ensure that no write to the index occurs after we delete it.
-        if let State::Processing(uuid, _) = *self.state.read() {
-            if uuid == index_uuid {
-                // wait for a write lock, do nothing with it.
-                self.state.write();
-            }
+        if self.state.read().processing(index_uuid).is_some() {
+            // wait for a write lock, do nothing with it.
+            self.processing_lock.write();
         }
 
         // Finally, remove any outstanding update files. This must be done after waiting for the
@@ -491,7 +1174,16 @@ impl UpdateStore {
         Ok(())
     }
 
-    pub fn snapshot(&self, indexes: Vec<Index>, path: impl AsRef<Path>) -> Result<()> {
+    /// Snapshots the updates and the indexes of `uuids`. The indexes are opened one at a time,
+    /// so that they can be closed once snapshotted if too many indexes are opened.
+    pub fn snapshot(
+        &self,
+        uuids: &HashSet<Uuid>,
+        index_resolver: &HardStateIndexResolver,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        // Wait for the processing updates to be done.
+        let _processing_lock = self.processing_lock.write();
         let state_lock = self.state.write();
         state_lock.swap(State::Snapshoting);
 
@@ -509,7 +1201,6 @@ impl UpdateStore {
 
         let pendings = self.pending_queue.iter(&txn)?.lazily_decode_data();
 
-        let uuids: HashSet<_> = indexes.iter().map(|i| i.uuid).collect();
         for entry in pendings {
             let ((_, uuid, _), pending) = entry?;
             if uuids.contains(&uuid) {
@@ -525,11 +1216,11 @@ impl UpdateStore {
             }
         }
 
-        let path = path.as_ref().to_owned();
-        indexes
-            .par_iter()
-            .try_for_each(|index| index.snapshot(path.clone()))
-            .unwrap();
+        let handle = Handle::current();
+        for uuid in uuids {
+            let index = handle.block_on(index_resolver.get_index_by_uuid(*uuid))?;
+            index.snapshot(&path)?;
+        }
 
         Ok(())
     }
@@ -549,14 +1240,90 @@ impl UpdateStore {
             }
         }
         let processing = match *self.state.read() {
-            State::Processing(uuid, _) => Some(uuid),
-            _ => None,
+            State::Processing(ref batches) => batches.keys().copied().collect(),
+            _ => HashSet::new(),
         };
 
         Ok(UpdateStoreInfo { size, processing })
     }
 }
 
+/// Returns `true` if `update` can be indexed along with `first`: both must be document
+/// additions with the same options. The versioned additions are never batched since their
+/// documents must be compared to the ones indexed by the previous updates.
+fn can_be_batched(first: &Update, update: &Update) -> bool {
+    match (first, update) {
+        (
+            Update::DocumentAddition {
+                primary_key,
+                method,
+                version_field: None,
+                lenient,
+                ..
+            },
+            Update::DocumentAddition {
+                primary_key: other_primary_key,
+                method: other_method,
+                version_field: None,
+                lenient: other_lenient,
+                ..
+            },
+        ) => primary_key == other_primary_key && method == other_method && lenient == other_lenient,
+        _ => false,
+    }
+}
+
+/// The kinds of updates, which tell whether two updates can be processed in any order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum UpdateKind {
+    Settings,
+    DocumentDeletion,
+    /// A document addition or clear.
+    OtherDocumentChange,
+    /// The updates that must be processed in order with every other update.
+    Other,
+}
+
+impl UpdateKind {
+    fn of(update: &Update) -> Self {
+        match update {
+            Update::Settings(_) => Self::Settings,
+            Update::DeleteDocuments(_) => Self::DocumentDeletion,
+            Update::DocumentAddition { .. } | Update::ClearDocuments => Self::OtherDocumentChange,
+            Update::DocumentEdition { .. } | Update::PrimaryKeyChange { .. } => Self::Other,
+        }
+    }
+
+    fn is_document_change(self) -> bool {
+        matches!(self, Self::DocumentDeletion | Self::OtherDocumentChange)
+    }
+
+    /// Returns `true` if the result of two updates of an index doesn't depend on the order in
+    /// which they are processed. The settings don't change how the documents are added or
+    /// deleted, and the deletions of documents don't depend on each other.
+    fn commutes(self, other: Self) -> bool {
+        match (self, other) {
+            (Self::Settings, other) => other.is_document_change(),
+            (update, Self::Settings) => update.is_document_change(),
+            (Self::DocumentDeletion, Self::DocumentDeletion) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Prunes the update history according to `retention`, logging the errors since they must not
+/// interrupt the processing of the updates.
+async fn prune_history(update_store: Arc<UpdateStore>, retention: RetentionPolicy) {
+    let res = tokio::task::spawn_blocking(move || update_store.prune(&retention, None))
+        .await
+        .expect("Fatal error pruning the update history.");
+    match res {
+        Ok(0) => (),
+        Ok(deleted) => info!("Pruned {} updates from the update history.", deleted),
+        Err(e) => error!("Error while pruning the update history: {}", e),
+    }
+}
+
 //#[cfg(test)]
 //mod test {
 //use super::*;
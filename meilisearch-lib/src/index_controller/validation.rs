@@ -0,0 +1,210 @@
+//! Validation of document addition payloads without registering any update. It catches the
+//! errors that would otherwise only be reported once the update is processed.
+
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+
+use meilisearch_error::{Code, ErrorCode};
+use milli::documents::DocumentBatchReader;
+use milli::FieldId;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::document_formats::{read_csv, read_json, read_ndjson};
+
+use super::error::{IndexControllerError, Result};
+use super::updates::StreamReader;
+use super::{DocumentAdditionFormat, Payload};
+
+/// Maximum number of invalid documents reported by a validation.
+pub const MAX_REPORTED_ERRORS: usize = 20;
+
+const DEFAULT_PRIMARY_KEY_NAME: &str = "id";
+const GEO_FIELD: &str = "_geo";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentsValidation {
+    pub primary_key: String,
+    pub nb_documents: usize,
+    pub nb_invalid_documents: usize,
+    /// The errors of the first `MAX_REPORTED_ERRORS` invalid documents.
+    pub errors: Vec<InvalidDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidDocument {
+    /// Position of the document in the payload.
+    pub position: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<Value>,
+    pub message: String,
+    pub error_code: String,
+}
+
+impl InvalidDocument {
+    pub(crate) fn new(
+        position: usize,
+        document: &Map<String, Value>,
+        primary_key: &str,
+        error: DocumentValidationError,
+    ) -> Self {
+        Self {
+            position,
+            document_id: document.get(primary_key).cloned(),
+            message: error.to_string(),
+            error_code: error.error_name(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DocumentValidationError {
+    #[error("The primary key of the documents could not be inferred.")]
+    MissingPrimaryKey,
+    #[error("The document is missing its primary key `{0}`.")]
+    MissingDocumentId(String),
+    #[error(
+        "Document identifier `{0}` is invalid. A document identifier can be of type integer or \
+        string, only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and \
+        underscores (_)."
+    )]
+    InvalidDocumentId(Value),
+    #[error("The `_geo` field must be an object containing a `lat` and a `lng` number.")]
+    InvalidGeoField,
+}
+
+impl ErrorCode for DocumentValidationError {
+    fn error_code(&self) -> Code {
+        match self {
+            Self::MissingPrimaryKey => Code::MissingPrimaryKey,
+            Self::MissingDocumentId(_) => Code::MissingDocumentId,
+            Self::InvalidDocumentId(_) => Code::InvalidDocumentId,
+            Self::InvalidGeoField => Code::InvalidGeoField,
+        }
+    }
+}
+
+/// Reads the payload the same way a document addition would, and checks each of its documents.
+/// `primary_key` is the primary key of the index if it has one, or the one provided by the user.
+pub fn validate_documents(
+    payload: Payload,
+    format: DocumentAdditionFormat,
+    primary_key: Option<String>,
+) -> Result<DocumentsValidation> {
+    let mut reader = BufReader::new(StreamReader::new(payload));
+    reader.fill_buf()?;
+    if reader.buffer().is_empty() {
+        return Err(IndexControllerError::MissingPayload(format));
+    }
+
+    let mut documents_file = tempfile::tempfile()?;
+    match format {
+        DocumentAdditionFormat::Json => read_json(reader, &mut documents_file)?,
+        DocumentAdditionFormat::Csv => read_csv(reader, &mut documents_file)?,
+        DocumentAdditionFormat::Ndjson => read_ndjson(reader, &mut documents_file)?,
+    }
+    documents_file.seek(SeekFrom::Start(0))?;
+
+    let mut documents = DocumentBatchReader::from_reader(documents_file)?;
+    let mut primary_key = primary_key;
+    let mut document = Map::new();
+    let mut nb_documents = 0;
+    let mut nb_invalid_documents = 0;
+    let mut errors = Vec::new();
+
+    while let Some((index, obkv)) = documents.next_document_with_index()? {
+        let primary_key = match primary_key {
+            Some(ref primary_key) => primary_key.as_str(),
+            None => primary_key
+                .insert(infer_primary_key(index.iter())?)
+                .as_str(),
+        };
+
+        document.clear();
+        for (field_id, content) in obkv.iter() {
+            if let Some(field_name) = index.get_by_left(&field_id) {
+                document.insert(field_name.to_string(), serde_json::from_slice(content)?);
+            }
+        }
+
+        if let Err(error) = validate_document(&document, primary_key) {
+            nb_invalid_documents += 1;
+            if errors.len() < MAX_REPORTED_ERRORS {
+                errors.push(InvalidDocument::new(
+                    nb_documents,
+                    &document,
+                    primary_key,
+                    error,
+                ));
+            }
+        }
+
+        nb_documents += 1;
+    }
+
+    Ok(DocumentsValidation {
+        primary_key: primary_key.ok_or(DocumentValidationError::MissingPrimaryKey)?,
+        nb_documents,
+        nb_invalid_documents,
+        errors,
+    })
+}
+
+/// Infers the primary key the same way milli does: the first field whose name contains `id`.
+pub(crate) fn infer_primary_key<'a>(
+    fields: impl Iterator<Item = (&'a FieldId, &'a String)>,
+) -> std::result::Result<String, DocumentValidationError> {
+    let mut fields: Vec<_> = fields.collect();
+    fields.sort_by_key(|(id, _)| **id);
+    fields
+        .into_iter()
+        .map(|(_, name)| name)
+        .find(|name| name.to_lowercase().contains(DEFAULT_PRIMARY_KEY_NAME))
+        .cloned()
+        .ok_or(DocumentValidationError::MissingPrimaryKey)
+}
+
+/// Whether `id` can be used as a document id: an integer, or a non-empty string composed of
+/// alphanumeric characters, hyphens (-) and underscores (_).
+pub(crate) fn is_valid_document_id(id: &Value) -> bool {
+    match id {
+        Value::String(id) => {
+            !id.is_empty()
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        }
+        Value::Number(id) => id.is_u64() || id.is_i64(),
+        _ => false,
+    }
+}
+
+/// Checks the document id and geo field, which would otherwise make the whole update fail.
+pub(crate) fn validate_document(
+    document: &Map<String, Value>,
+    primary_key: &str,
+) -> std::result::Result<(), DocumentValidationError> {
+    match document.get(primary_key) {
+        Some(id) if is_valid_document_id(id) => {}
+        Some(id) => return Err(DocumentValidationError::InvalidDocumentId(id.clone())),
+        None => {
+            return Err(DocumentValidationError::MissingDocumentId(
+                primary_key.to_string(),
+            ))
+        }
+    }
+
+    if let Some(geo) = document.get(GEO_FIELD) {
+        let is_coordinate = |value: Option<&Value>| match value {
+            Some(Value::Number(_)) => true,
+            Some(Value::String(s)) => s.parse::<f64>().is_ok(),
+            _ => false,
+        };
+        if !is_coordinate(geo.get("lat")) || !is_coordinate(geo.get("lng")) {
+            return Err(DocumentValidationError::InvalidGeoField);
+        }
+    }
+
+    Ok(())
+}